@@ -1,8 +0,0 @@
-use bevy::prelude::*;
-
-#[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States)]
-pub enum GameState {
-    AssetLoading,
-    #[default]
-    Playing,
-}
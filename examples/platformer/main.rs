@@ -1,26 +1,16 @@
+use bean_platformer::{game_flow::GameFile, replay::ReplayPlugin, FrijoleroPlugins};
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
+#[cfg(feature = "dev")]
 use bevy_rapier2d::prelude::*;
 
-mod camera;
-mod climbing;
-mod colliders;
-mod enemy;
-mod game_flow;
-mod ground_detection;
-mod inventory;
-mod misc_objects;
-mod player;
-mod walls;
-
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
-        .add_plugins((
-            LdtkPlugin,
-            RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0),
-            RapierDebugRenderPlugin::default(),
-        ))
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
+        .add_plugins(FrijoleroPlugins.build().disable::<ReplayPlugin>())
+        .insert_resource(GameFile {
+            path: "examples/platformer/example.ldtk".into(),
+        })
         .insert_resource(LevelSelection::Uid(0))
         .insert_resource(LdtkSettings {
             level_spawn_behavior: LevelSpawnBehavior::UseWorldTranslation {
@@ -28,15 +18,8 @@ fn main() {
             },
             set_clear_color: SetClearColor::FromLevelBackground,
             ..Default::default()
-        })
-        .add_plugins(game_flow::GameFlowPlugin)
-        .add_plugins(player::PlayerPlugin)
-        .add_plugins(ground_detection::GroundDetectionPlugin)
-        .add_plugins(walls::WallsPlugin)
-        .add_plugins(climbing::ClimbingPlugin)
-        .add_plugins(enemy::EnemyPlugin)
-        .add_plugins(misc_objects::MiscObjectsPlugin)
-        .add_systems(Update, inventory::dbg_print_inventory)
-        .add_systems(Update, camera::camera_fit_inside_current_level)
-        .run();
+        });
+    #[cfg(feature = "dev")]
+    app.add_plugins(RapierDebugRenderPlugin::default());
+    app.run();
 }
@@ -1,7 +1,8 @@
 use bevy::reflect::Reflect;
 use leafwing_input_manager::Actionlike;
+use serde::{Deserialize, Serialize};
 
-#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
+#[derive(Actionlike, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect, Serialize, Deserialize)]
 pub enum PlatformerAction {
     Right,
     Left,
@@ -9,6 +10,28 @@ pub enum PlatformerAction {
     Up,
     Jump,
 
+    /// debug actions, only used in builds with the `dev` feature
     RespawnLevel,
     RespawnWorld,
 }
+impl PlatformerAction {
+    /// every action the build uses, in the order they're shown in the controls
+    /// menu
+    pub const ALL: &[PlatformerAction] = &[
+        PlatformerAction::Left,
+        PlatformerAction::Right,
+        PlatformerAction::Up,
+        PlatformerAction::Down,
+        PlatformerAction::Jump,
+        #[cfg(feature = "dev")]
+        PlatformerAction::RespawnLevel,
+        #[cfg(feature = "dev")]
+        PlatformerAction::RespawnWorld,
+    ];
+
+    /// the debug actions, left out of builds without the `dev` feature
+    pub const DEV: [PlatformerAction; 2] = [
+        PlatformerAction::RespawnLevel,
+        PlatformerAction::RespawnWorld,
+    ];
+}
@@ -0,0 +1,650 @@
+use std::{fmt, time::Duration};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+    utils::HashMap,
+};
+use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+// ANIMATION LIBRARY
+
+/// A set of named animation clips for a sprite sheet.
+///
+/// Loaded from either:
+/// * `*.anim.json` files, see [`AnimationLibraryLoader`]
+/// * Aseprite JSON sheet exports (`*.aseprite.json`), see [`AsepriteLoader`]
+#[derive(Asset, TypePath, Clone, PartialEq, Debug, Default)]
+pub struct AnimationLibrary {
+    pub clips: HashMap<String, SpriteClip>,
+}
+
+/// A sequence of sprite sheet frames
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SpriteClip {
+    pub frames: Vec<AnimationFrame>,
+    pub mode: PlaybackMode,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AnimationFrame {
+    /// index into the sprite's texture atlas
+    pub index: usize,
+    pub duration: Duration,
+    /// names of the [`AnimationFrameEvent`]s to send when this frame shows,
+    /// e.g. "footstep" or "attack"
+    pub events: Vec<String>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackMode {
+    /// start over from the first frame after the last one
+    #[default]
+    Loop,
+    /// stop on the last frame
+    Once,
+}
+
+#[derive(Debug)]
+pub enum AnimationLoadError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+impl fmt::Display for AnimationLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read animation file: {err}"),
+            Self::Json(err) => write!(f, "could not parse animation file: {err}"),
+        }
+    }
+}
+impl std::error::Error for AnimationLoadError {}
+impl From<std::io::Error> for AnimationLoadError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+impl From<serde_json::Error> for AnimationLoadError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+// .anim.json
+
+/// Loads an [`AnimationLibrary`] from a `*.anim.json` file. Each clip lists its
+/// frames, either as a list of atlas indexes or as an inclusive range, and
+/// either a single `fps` or a per-frame `durations_ms`. Clips can also send
+/// named events on some of their frames, listed by position in the clip:
+///
+/// ```json
+/// {
+///   "clips": {
+///     "idle": { "frames": [0, 3], "fps": 3 },
+///     "run": {
+///       "frames": { "from": 1, "to": 4 },
+///       "durations_ms": [60, 60, 60, 80],
+///       "events": { "footstep": [0, 2] }
+///     },
+///     "land": { "frames": [5, 6], "fps": 12, "mode": "once" }
+///   }
+/// }
+/// ```
+#[derive(Default)]
+pub struct AnimationLibraryLoader;
+
+#[derive(Deserialize)]
+struct AnimationLibraryDef {
+    clips: HashMap<String, SpriteClipDef>,
+}
+
+#[derive(Deserialize)]
+struct SpriteClipDef {
+    frames: FramesDef,
+    #[serde(default)]
+    fps: Option<f32>,
+    #[serde(default)]
+    durations_ms: Option<Vec<u64>>,
+    #[serde(default)]
+    mode: PlaybackMode,
+    /// event name to the positions of the frames that send it
+    #[serde(default)]
+    events: HashMap<String, Vec<usize>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FramesDef {
+    List(Vec<usize>),
+    Range { from: usize, to: usize },
+}
+
+/// used when a clip specifies neither fps nor durations
+const DEFAULT_FPS: f32 = 10.;
+
+impl From<SpriteClipDef> for SpriteClip {
+    fn from(clip_def: SpriteClipDef) -> Self {
+        let indexes: Vec<usize> = match clip_def.frames {
+            FramesDef::List(indexes) => indexes,
+            FramesDef::Range { from, to } => (from..=to).collect(),
+        };
+        let default_duration =
+            Duration::from_secs_f32(1. / clip_def.fps.unwrap_or(DEFAULT_FPS).max(f32::EPSILON));
+        let frames = indexes
+            .into_iter()
+            .enumerate()
+            .map(|(i, index)| AnimationFrame {
+                index,
+                duration: clip_def
+                    .durations_ms
+                    .as_ref()
+                    .and_then(|durations| durations.get(i))
+                    .map(|ms| Duration::from_millis(*ms))
+                    .unwrap_or(default_duration),
+                events: clip_def
+                    .events
+                    .iter()
+                    .filter(|(_, positions)| positions.contains(&i))
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+            })
+            .collect();
+        SpriteClip {
+            frames,
+            mode: clip_def.mode,
+        }
+    }
+}
+
+impl AssetLoader for AnimationLibraryLoader {
+    type Asset = AnimationLibrary;
+    type Settings = ();
+    type Error = AnimationLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let library_def: AnimationLibraryDef = serde_json::from_slice(&bytes)?;
+        Ok(AnimationLibrary {
+            clips: library_def
+                .clips
+                .into_iter()
+                .map(|(name, clip_def)| (name, clip_def.into()))
+                .collect(),
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.json"]
+    }
+}
+
+// .aseprite.json
+
+/// Loads an [`AnimationLibrary`] from an Aseprite JSON sheet export
+/// (`File > Export Sprite Sheet`, with "JSON Data" & "Tags" enabled). Both the
+/// "Hash" and "Array" formats are supported. Every tag becomes a clip, and
+/// frame durations come from Aseprite.
+///
+/// Frames are mapped to atlas indexes by their position in the sheet, so the
+/// sprite's atlas should be a grid with the same frame size.
+#[derive(Default)]
+pub struct AsepriteLoader;
+
+#[derive(Deserialize)]
+struct AsepriteSheet {
+    frames: AsepriteFrames,
+    meta: AsepriteMeta,
+}
+
+#[derive(Deserialize)]
+struct AsepriteFrame {
+    frame: AsepriteRect,
+    duration: u64,
+}
+
+#[derive(Deserialize)]
+struct AsepriteRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct AsepriteSize {
+    w: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    size: AsepriteSize,
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteTag {
+    name: String,
+    from: usize,
+    to: usize,
+    #[serde(default)]
+    direction: AsepriteDirection,
+    /// how many times to play the tag, missing or "0" means forever
+    #[serde(default)]
+    repeat: Option<String>,
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AsepriteDirection {
+    #[default]
+    Forward,
+    Reverse,
+    Pingpong,
+    PingpongReverse,
+}
+
+/// Aseprite frames, in timeline order. The "Hash" export is an object keyed
+/// by filename, so we have to keep the order the keys appear in
+struct AsepriteFrames(Vec<AsepriteFrame>);
+
+impl<'de> Deserialize<'de> for AsepriteFrames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FramesVisitor;
+
+        impl<'de> Visitor<'de> for FramesVisitor {
+            type Value = AsepriteFrames;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array or map of aseprite frames")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(frame) = seq.next_element()? {
+                    frames.push(frame);
+                }
+                Ok(AsepriteFrames(frames))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut frames = Vec::new();
+                while let Some((_filename, frame)) = map.next_entry::<String, AsepriteFrame>()? {
+                    frames.push(frame);
+                }
+                Ok(AsepriteFrames(frames))
+            }
+        }
+
+        deserializer.deserialize_any(FramesVisitor)
+    }
+}
+
+impl From<AsepriteSheet> for AnimationLibrary {
+    fn from(sheet: AsepriteSheet) -> Self {
+        let frames: Vec<AnimationFrame> = sheet
+            .frames
+            .0
+            .iter()
+            .map(|aseprite_frame| {
+                let rect = &aseprite_frame.frame;
+                let columns = (sheet.meta.size.w / rect.w.max(1)).max(1);
+                AnimationFrame {
+                    index: ((rect.y / rect.h.max(1)) * columns + rect.x / rect.w.max(1)) as usize,
+                    duration: Duration::from_millis(aseprite_frame.duration),
+                    events: Vec::new(),
+                }
+            })
+            .collect();
+
+        let clips = sheet
+            .meta
+            .frame_tags
+            .into_iter()
+            .map(|tag| {
+                let to = tag.to.min(frames.len().saturating_sub(1));
+                let forward: Vec<AnimationFrame> =
+                    frames.get(tag.from..=to).unwrap_or_default().to_vec();
+                let reverse: Vec<AnimationFrame> = forward.iter().rev().cloned().collect();
+
+                // ping pong doesn't repeat the frames at either end
+                let ping_pong = |there: &[AnimationFrame], back: &[AnimationFrame]| {
+                    let inner = if back.len() > 2 {
+                        &back[1..back.len() - 1]
+                    } else {
+                        &[]
+                    };
+                    [there, inner].concat()
+                };
+                let cycle = match tag.direction {
+                    AsepriteDirection::Forward => forward,
+                    AsepriteDirection::Reverse => reverse,
+                    AsepriteDirection::Pingpong => ping_pong(&forward, &reverse),
+                    AsepriteDirection::PingpongReverse => ping_pong(&reverse, &forward),
+                };
+
+                // a tag that repeats a fixed number of times plays once, with
+                // its frames repeated
+                let repeat: usize = tag
+                    .repeat
+                    .as_deref()
+                    .and_then(|repeat| repeat.parse().ok())
+                    .unwrap_or(0);
+                let clip = if repeat == 0 {
+                    SpriteClip {
+                        frames: cycle,
+                        mode: PlaybackMode::Loop,
+                    }
+                } else {
+                    SpriteClip {
+                        frames: (0..repeat).flat_map(|_| cycle.iter().cloned()).collect(),
+                        mode: PlaybackMode::Once,
+                    }
+                };
+                (tag.name, clip)
+            })
+            .collect();
+
+        AnimationLibrary { clips }
+    }
+}
+
+impl AssetLoader for AsepriteLoader {
+    type Asset = AnimationLibrary;
+    type Settings = ();
+    type Error = AnimationLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let sheet: AsepriteSheet = serde_json::from_slice(&bytes)?;
+        Ok(sheet.into())
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["aseprite.json"]
+    }
+}
+
+// SPRITE ANIMATION
+
+/// Plays clips from an [`AnimationLibrary`] on this entity's sprite. Works on
+/// any entity with a texture atlas sprite.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct SpriteAnimation {
+    pub library: Handle<AnimationLibrary>,
+    clip: String,
+    frame: usize,
+    elapsed: Duration,
+    finished: bool,
+    // whether the current frame has been shown yet
+    frame_shown: bool,
+}
+impl SpriteAnimation {
+    pub fn new(library: Handle<AnimationLibrary>, clip: impl Into<String>) -> Self {
+        Self {
+            library,
+            clip: clip.into(),
+            frame: 0,
+            elapsed: Duration::ZERO,
+            finished: false,
+            frame_shown: false,
+        }
+    }
+
+    /// the name of the clip currently playing
+    pub fn clip(&self) -> &str {
+        &self.clip
+    }
+
+    /// which frame of the clip is showing
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// if the clip plays once, whether it's reached the last frame
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// switch to the provided clip, starting from its first frame. does
+    /// nothing if the clip is already playing
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip {
+            self.clip = clip.to_string();
+            self.frame = 0;
+            self.elapsed = Duration::ZERO;
+            self.finished = false;
+            self.frame_shown = false;
+        }
+    }
+
+    /// advance the animation. returns true if a new frame started showing,
+    /// including the first frame of a clip & the first frame of each loop
+    pub fn tick(&mut self, clip: &SpriteClip, delta: Duration) -> bool {
+        if clip.frames.is_empty() || self.finished {
+            return false;
+        }
+
+        // if the clip changed out from under us, start it over
+        if self.frame >= clip.frames.len() {
+            self.frame = 0;
+            self.frame_shown = false;
+        }
+
+        let mut new_frame = !self.frame_shown;
+        self.frame_shown = true;
+        self.elapsed += delta;
+        loop {
+            let duration = clip.frames[self.frame].duration;
+            if self.elapsed < duration || duration.is_zero() {
+                break;
+            }
+            self.elapsed -= duration;
+
+            if self.frame + 1 < clip.frames.len() {
+                self.frame += 1;
+            } else {
+                match clip.mode {
+                    PlaybackMode::Loop => self.frame = 0,
+                    PlaybackMode::Once => {
+                        self.finished = true;
+                        break;
+                    }
+                }
+            }
+            new_frame = true;
+        }
+        new_frame
+    }
+}
+
+/// sent when a frame with events starts showing, once per event on the frame
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct AnimationFrameEvent {
+    pub ent: Entity,
+    pub clip: String,
+    pub name: String,
+}
+
+/// advance every sprite animation, update the atlas index of its sprite &
+/// send the events of any frame that started showing
+pub fn animate_sprites(
+    time: Res<Time>,
+    libraries: Res<Assets<AnimationLibrary>>,
+    mut frame_events: EventWriter<AnimationFrameEvent>,
+    mut query: Query<(Entity, &mut SpriteAnimation, &mut Sprite)>,
+) {
+    for (ent, mut animation, mut sprite) in &mut query {
+        let Some(clip) = libraries
+            .get(&animation.library)
+            .and_then(|library| library.clips.get(animation.clip()))
+        else {
+            continue;
+        };
+        let new_frame = animation.tick(clip, time.delta());
+        let Some(frame) = clip.frames.get(animation.frame()) else {
+            continue;
+        };
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            if atlas.index != frame.index {
+                atlas.index = frame.index;
+            }
+        }
+        if new_frame {
+            for name in &frame.events {
+                frame_events.send(AnimationFrameEvent {
+                    ent,
+                    clip: animation.clip().to_string(),
+                    name: name.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// loads animation libraries & plays sprite animations
+pub struct SpriteAnimationPlugin;
+
+impl Plugin for SpriteAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<AnimationLibrary>()
+            .add_event::<AnimationFrameEvent>()
+            .init_asset_loader::<AnimationLibraryLoader>()
+            .init_asset_loader::<AsepriteLoader>()
+            .add_systems(Update, animate_sprites);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn clip(durations_ms: &[u64], mode: PlaybackMode) -> SpriteClip {
+        SpriteClip {
+            frames: durations_ms
+                .iter()
+                .enumerate()
+                .map(|(index, duration)| AnimationFrame {
+                    index,
+                    duration: ms(*duration),
+                    events: Vec::new(),
+                })
+                .collect(),
+            mode,
+        }
+    }
+
+    fn indexes(clip: &SpriteClip) -> Vec<usize> {
+        clip.frames.iter().map(|frame| frame.index).collect()
+    }
+
+    #[test]
+    fn anim_json_clips_use_ranges_durations_and_events() {
+        let library_def: AnimationLibraryDef = serde_json::from_str(
+            r#"{ "clips": { "run": {
+                "frames": { "from": 1, "to": 3 },
+                "fps": 4,
+                "durations_ms": [60, 80],
+                "events": { "footstep": [0, 2] },
+                "mode": "once"
+            } } }"#,
+        )
+        .unwrap();
+        let run: SpriteClip = library_def.clips.into_values().next().unwrap().into();
+
+        assert_eq!(indexes(&run), vec![1, 2, 3]);
+        let durations: Vec<Duration> = run.frames.iter().map(|frame| frame.duration).collect();
+        // frames without a duration fall back to the fps
+        assert_eq!(durations, vec![ms(60), ms(80), ms(250)]);
+        assert_eq!(run.frames[0].events, vec!["footstep".to_string()]);
+        assert!(run.frames[1].events.is_empty());
+        assert_eq!(run.frames[2].events, vec!["footstep".to_string()]);
+        assert_eq!(run.mode, PlaybackMode::Once);
+    }
+
+    #[test]
+    fn aseprite_tags_become_clips() {
+        // a 2x2 sheet of 16px frames, exported as a hash
+        let sheet: AsepriteSheet = serde_json::from_str(
+            r#"{
+                "frames": {
+                    "bean 0.aseprite": { "frame": { "x": 0, "y": 0, "w": 16, "h": 16 }, "duration": 100 },
+                    "bean 1.aseprite": { "frame": { "x": 16, "y": 0, "w": 16, "h": 16 }, "duration": 150 },
+                    "bean 2.aseprite": { "frame": { "x": 0, "y": 16, "w": 16, "h": 16 }, "duration": 100 },
+                    "bean 3.aseprite": { "frame": { "x": 16, "y": 16, "w": 16, "h": 16 }, "duration": 100 }
+                },
+                "meta": {
+                    "size": { "w": 32, "h": 32 },
+                    "frameTags": [
+                        { "name": "walk", "from": 0, "to": 3, "direction": "pingpong" },
+                        { "name": "blink", "from": 2, "to": 3, "direction": "reverse", "repeat": "2" }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+        let library = AnimationLibrary::from(sheet);
+
+        let walk = &library.clips["walk"];
+        assert_eq!(indexes(walk), vec![0, 1, 2, 3, 2, 1]);
+        assert_eq!(walk.frames[1].duration, ms(150));
+        assert_eq!(walk.mode, PlaybackMode::Loop);
+
+        let blink = &library.clips["blink"];
+        assert_eq!(indexes(blink), vec![3, 2, 3, 2]);
+        assert_eq!(blink.mode, PlaybackMode::Once);
+    }
+
+    #[test]
+    fn looping_clips_wrap_around() {
+        let clip = clip(&[100, 100], PlaybackMode::Loop);
+        let mut animation = SpriteAnimation::new(Handle::default(), "idle");
+
+        // the first frame counts as new
+        assert!(animation.tick(&clip, ms(50)));
+        assert_eq!(animation.frame(), 0);
+        assert!(!animation.tick(&clip, ms(40)));
+        assert!(animation.tick(&clip, ms(20)));
+        assert_eq!(animation.frame(), 1);
+        assert!(animation.tick(&clip, ms(100)));
+        assert_eq!(animation.frame(), 0);
+        assert!(!animation.finished());
+    }
+
+    #[test]
+    fn clips_played_once_stop_on_the_last_frame() {
+        let clip = clip(&[100, 100], PlaybackMode::Once);
+        let mut animation = SpriteAnimation::new(Handle::default(), "land");
+
+        animation.tick(&clip, ms(500));
+        assert_eq!(animation.frame(), 1);
+        assert!(animation.finished());
+        assert!(!animation.tick(&clip, ms(100)));
+
+        // playing another clip starts it over
+        animation.play("idle");
+        assert!(!animation.finished());
+        assert_eq!(animation.frame(), 0);
+    }
+}
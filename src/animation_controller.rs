@@ -0,0 +1,507 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::dynamics::Velocity;
+
+use crate::{
+    animation::{animate_sprites, AnimationLibrary, SpriteAnimation},
+    game_flow::GameFile,
+};
+
+// animation controller constants
+
+/// Entity field pointing at the entity's animation library, either a
+/// `*.anim.json` or `*.aseprite.json` file. Paths are relative to the LDtk
+/// project file. Entities without it aren't animated.
+pub(crate) const ANIMATION_FIELD: &str = "animation";
+
+/// how fast something has to move before it counts as moving
+const MOVING_SPEED: f32 = 1.;
+
+/// When to move from one animation state to another
+#[derive(Clone, PartialEq, Debug)]
+pub enum AnimationCondition {
+    /// moving faster than [`MOVING_SPEED`]
+    Moving,
+    /// not moving faster than [`MOVING_SPEED`]
+    Stopped,
+    /// the current clip plays once & has reached its last frame
+    Finished,
+    /// the flag is set, see [`AnimationController::set_flag`]
+    Flag(String),
+    /// the flag isn't set
+    NotFlag(String),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AnimationTransition {
+    /// the state this transition leaves, or None for any state
+    pub from: Option<String>,
+    pub to: String,
+    pub condition: AnimationCondition,
+}
+
+/// A state machine picking which clip a [`SpriteAnimation`] plays. Each state
+/// plays the clip with the same name. Every frame, the first transition out
+/// of the current state whose condition is met is taken. Transitions into
+/// states the animation library doesn't have a clip for are skipped, so an
+/// entity can use a library with only some of the states.
+///
+/// For LDtk entities, the controller is picked by the entity's identifier &
+/// the clips are loaded from the entity's `animation` field.
+#[derive(Clone, PartialEq, Debug, Default, Component)]
+pub struct AnimationController {
+    pub transitions: Vec<AnimationTransition>,
+    /// face the sprite in the direction it's moving
+    pub flip_from_velocity: bool,
+
+    state: String,
+    flags: HashSet<String>,
+}
+
+impl AnimationController {
+    pub fn new(initial_state: impl Into<String>) -> Self {
+        Self {
+            state: initial_state.into(),
+            ..default()
+        }
+    }
+
+    /// add a transition from the `from` state, or from any state if `from` is
+    /// None
+    pub fn with_transition(
+        mut self,
+        from: Option<&str>,
+        to: &str,
+        condition: AnimationCondition,
+    ) -> Self {
+        self.transitions.push(AnimationTransition {
+            from: from.map(str::to_string),
+            to: to.to_string(),
+            condition,
+        });
+        self
+    }
+
+    pub fn with_flip_from_velocity(mut self) -> Self {
+        self.flip_from_velocity = true;
+        self
+    }
+
+    /// the current state
+    pub fn state(&self) -> &str {
+        &self.state
+    }
+
+    /// set or clear a flag, for [`AnimationCondition::Flag`] transitions
+    pub fn set_flag(&mut self, flag: &str, set: bool) {
+        if set {
+            self.flags.insert(flag.to_string());
+        } else {
+            self.flags.remove(flag);
+        }
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+
+    fn condition_met(
+        &self,
+        condition: &AnimationCondition,
+        velocity: Vec2,
+        animation: &SpriteAnimation,
+    ) -> bool {
+        match condition {
+            AnimationCondition::Moving => velocity.length() > MOVING_SPEED,
+            AnimationCondition::Stopped => velocity.length() <= MOVING_SPEED,
+            AnimationCondition::Finished => animation.finished(),
+            AnimationCondition::Flag(flag) => self.has_flag(flag),
+            AnimationCondition::NotFlag(flag) => !self.has_flag(flag),
+        }
+    }
+}
+
+/// walks around: idles while stopped, walks while moving
+fn walker_controller() -> AnimationController {
+    AnimationController::new("idle")
+        .with_transition(Some("idle"), "walk", AnimationCondition::Moving)
+        .with_transition(Some("walk"), "idle", AnimationCondition::Stopped)
+        .with_flip_from_velocity()
+}
+
+// Constructors for `#[with(...)]` on each kind's bundle
+
+impl AnimationController {
+    /// set the "attack" flag to attack
+    pub fn mob(_: &EntityInstance) -> AnimationController {
+        walker_controller()
+            .with_transition(None, "attack", AnimationCondition::Flag("attack".into()))
+            .with_transition(Some("attack"), "idle", AnimationCondition::Finished)
+            .with_transition(
+                Some("attack"),
+                "idle",
+                AnimationCondition::NotFlag("attack".into()),
+            )
+    }
+
+    pub fn obstacle(_: &EntityInstance) -> AnimationController {
+        walker_controller()
+    }
+
+    pub fn platform(_: &EntityInstance) -> AnimationController {
+        AnimationController::new("idle")
+            .with_transition(Some("idle"), "move", AnimationCondition::Moving)
+            .with_transition(Some("move"), "idle", AnimationCondition::Stopped)
+    }
+
+    /// set the "open" flag to open the chest
+    pub fn chest(_: &EntityInstance) -> AnimationController {
+        AnimationController::new("closed")
+            .with_transition(
+                Some("closed"),
+                "open",
+                AnimationCondition::Flag("open".into()),
+            )
+            .with_transition(
+                Some("open"),
+                "closed",
+                AnimationCondition::NotFlag("open".into()),
+            )
+    }
+}
+
+/// sent when an animation controller changes state
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct AnimationStateEvent {
+    pub ent: Entity,
+    pub from: String,
+    pub to: String,
+}
+
+/// start animating LDtk entities that have an animation field
+fn load_entity_animations(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_file: Res<GameFile>,
+    query: Query<(Entity, &AnimationController, &EntityInstance), Added<AnimationController>>,
+) {
+    for (ent, controller, entity_instance) in &query {
+        let Ok(Some(path)) = entity_instance.get_maybe_file_path_field(ANIMATION_FIELD) else {
+            continue;
+        };
+        // Animation paths are relative to the project file
+        let project_dir = game_file.path.parent().unwrap_or(&game_file.path);
+        commands.entity(ent).insert(SpriteAnimation::new(
+            asset_server.load(project_dir.join(path)),
+            controller.state(),
+        ));
+    }
+}
+
+/// take the first transition that's met, & flip sprites to face the way
+/// they're moving
+fn update_animation_controllers(
+    libraries: Res<Assets<AnimationLibrary>>,
+    mut state_events: EventWriter<AnimationStateEvent>,
+    mut query: Query<(
+        Entity,
+        &mut AnimationController,
+        &mut SpriteAnimation,
+        &mut Sprite,
+        Option<&Velocity>,
+    )>,
+) {
+    for (ent, mut controller, mut animation, mut sprite, opt_velocity) in &mut query {
+        let Some(library) = libraries.get(&animation.library) else {
+            continue;
+        };
+        let velocity = opt_velocity
+            .map(|velocity| velocity.linvel)
+            .unwrap_or_default();
+
+        let next_state = controller
+            .transitions
+            .iter()
+            .filter(|transition| {
+                transition
+                    .from
+                    .as_ref()
+                    .is_none_or(|from| *from == controller.state)
+            })
+            .filter(|transition| {
+                transition.to != controller.state && library.clips.contains_key(&transition.to)
+            })
+            .find(|transition| {
+                controller.condition_met(&transition.condition, velocity, &animation)
+            })
+            .map(|transition| transition.to.clone());
+
+        if let Some(next_state) = next_state {
+            state_events.send(AnimationStateEvent {
+                ent,
+                from: controller.state.clone(),
+                to: next_state.clone(),
+            });
+            controller.state = next_state;
+        }
+        if animation.clip() != controller.state {
+            animation.play(&controller.state);
+        }
+
+        if controller.flip_from_velocity && velocity.x.abs() > MOVING_SPEED {
+            let flip_x = velocity.x < 0.;
+            if sprite.flip_x != flip_x {
+                sprite.flip_x = flip_x;
+            }
+        }
+    }
+}
+
+/// drives sprite animations of enemies & objects with animation state machines
+pub struct AnimationControllerPlugin;
+
+impl Plugin for AnimationControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AnimationStateEvent>().add_systems(
+            Update,
+            (load_entity_animations, update_animation_controllers)
+                .chain()
+                .before(animate_sprites),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::animation::{AnimationFrame, PlaybackMode, SpriteClip};
+
+    /// a library with a one frame clip for each of these states. "attack"
+    /// plays once, the rest loop
+    fn library(states: &[&str]) -> AnimationLibrary {
+        let clip = |state: &str| SpriteClip {
+            frames: vec![AnimationFrame {
+                index: 0,
+                duration: Duration::from_millis(100),
+                events: Vec::new(),
+            }],
+            mode: if state == "attack" {
+                PlaybackMode::Once
+            } else {
+                PlaybackMode::Loop
+            },
+        };
+        AnimationLibrary {
+            clips: states
+                .iter()
+                .map(|state| (state.to_string(), clip(state)))
+                .collect(),
+        }
+    }
+
+    /// an app that only updates animation controllers, with an entity using
+    /// this controller & a library with these states' clips
+    fn controlled_entity(controller: AnimationController, states: &[&str]) -> (App, Entity) {
+        let mut app = App::new();
+        let mut libraries = Assets::<AnimationLibrary>::default();
+        let handle = libraries.add(library(states));
+        app.insert_resource(libraries)
+            .add_event::<AnimationStateEvent>()
+            .add_systems(Update, update_animation_controllers);
+        let initial_state = controller.state().to_string();
+        let ent = app
+            .world_mut()
+            .spawn((
+                controller,
+                SpriteAnimation::new(handle, initial_state),
+                Sprite::default(),
+                Velocity::zero(),
+            ))
+            .id();
+        (app, ent)
+    }
+
+    fn set_velocity(app: &mut App, ent: Entity, linvel: Vec2) {
+        app.world_mut().get_mut::<Velocity>(ent).unwrap().linvel = linvel;
+    }
+
+    fn set_flag(app: &mut App, ent: Entity, flag: &str, set: bool) {
+        app.world_mut()
+            .get_mut::<AnimationController>(ent)
+            .unwrap()
+            .set_flag(flag, set);
+    }
+
+    /// update, returning the state the controller's in, the clip that's
+    /// playing & the state changes that were sent
+    fn update(app: &mut App, ent: Entity) -> (String, String, Vec<(String, String)>) {
+        app.update();
+        let world = app.world_mut();
+        let state_changes = world
+            .resource_mut::<Events<AnimationStateEvent>>()
+            .drain()
+            .map(|event| {
+                assert_eq!(event.ent, ent);
+                (event.from, event.to)
+            })
+            .collect();
+        (
+            world
+                .get::<AnimationController>(ent)
+                .unwrap()
+                .state()
+                .to_string(),
+            world
+                .get::<SpriteAnimation>(ent)
+                .unwrap()
+                .clip()
+                .to_string(),
+            state_changes,
+        )
+    }
+
+    fn change(from: &str, to: &str) -> Vec<(String, String)> {
+        vec![(from.to_string(), to.to_string())]
+    }
+
+    #[test]
+    fn walkers_walk_while_moving() {
+        let (mut app, ent) = controlled_entity(walker_controller(), &["idle", "walk"]);
+        let idle = || ("idle".to_string(), "idle".to_string(), Vec::new());
+        assert_eq!(update(&mut app, ent), idle());
+
+        set_velocity(&mut app, ent, Vec2::new(40., 0.));
+        assert_eq!(
+            update(&mut app, ent),
+            (
+                "walk".to_string(),
+                "walk".to_string(),
+                change("idle", "walk")
+            )
+        );
+        // no event while the state stays the same
+        assert_eq!(
+            update(&mut app, ent),
+            ("walk".to_string(), "walk".to_string(), Vec::new())
+        );
+
+        set_velocity(&mut app, ent, Vec2::new(MOVING_SPEED / 2., 0.));
+        assert_eq!(
+            update(&mut app, ent),
+            (
+                "idle".to_string(),
+                "idle".to_string(),
+                change("walk", "idle")
+            )
+        );
+    }
+
+    #[test]
+    fn flags_start_states_that_end_when_their_clip_finishes() {
+        let mob = AnimationController::mob(&EntityInstance::default());
+        let (mut app, ent) = controlled_entity(mob, &["idle", "walk", "attack"]);
+
+        set_flag(&mut app, ent, "attack", true);
+        assert_eq!(
+            update(&mut app, ent),
+            (
+                "attack".to_string(),
+                "attack".to_string(),
+                change("idle", "attack")
+            )
+        );
+        // the attack's still playing
+        assert_eq!(
+            update(&mut app, ent),
+            ("attack".to_string(), "attack".to_string(), Vec::new())
+        );
+
+        let clip = library(&["attack"]).clips["attack"].clone();
+        let world = app.world_mut();
+        let mut animation = world.get_mut::<SpriteAnimation>(ent).unwrap();
+        animation.tick(&clip, Duration::from_secs(1));
+        assert!(animation.finished());
+        assert_eq!(
+            update(&mut app, ent),
+            (
+                "idle".to_string(),
+                "idle".to_string(),
+                change("attack", "idle")
+            )
+        );
+    }
+
+    #[test]
+    fn clearing_a_flag_leaves_its_state() {
+        let chest = AnimationController::chest(&EntityInstance::default());
+        let (mut app, ent) = controlled_entity(chest, &["closed", "open"]);
+
+        set_flag(&mut app, ent, "open", true);
+        assert_eq!(
+            update(&mut app, ent),
+            (
+                "open".to_string(),
+                "open".to_string(),
+                change("closed", "open")
+            )
+        );
+        set_flag(&mut app, ent, "open", false);
+        assert_eq!(
+            update(&mut app, ent),
+            (
+                "closed".to_string(),
+                "closed".to_string(),
+                change("open", "closed")
+            )
+        );
+    }
+
+    #[test]
+    fn states_without_a_clip_are_skipped() {
+        let controller = AnimationController::new("idle")
+            .with_transition(Some("idle"), "run", AnimationCondition::Moving)
+            .with_transition(Some("idle"), "walk", AnimationCondition::Moving)
+            .with_transition(None, "attack", AnimationCondition::Flag("attack".into()));
+        let (mut app, ent) = controlled_entity(controller, &["idle", "walk"]);
+
+        // there's no attack clip
+        set_flag(&mut app, ent, "attack", true);
+        assert_eq!(
+            update(&mut app, ent),
+            ("idle".to_string(), "idle".to_string(), Vec::new())
+        );
+        // or run clip, so it walks
+        set_velocity(&mut app, ent, Vec2::new(40., 0.));
+        assert_eq!(
+            update(&mut app, ent),
+            (
+                "walk".to_string(),
+                "walk".to_string(),
+                change("idle", "walk")
+            )
+        );
+    }
+
+    #[test]
+    fn sprites_face_the_way_they_move() {
+        let flip_x = |app: &mut App, ent, linvel| {
+            set_velocity(app, ent, linvel);
+            app.update();
+            app.world().get::<Sprite>(ent).unwrap().flip_x
+        };
+
+        let (mut app, ent) = controlled_entity(walker_controller(), &["idle", "walk"]);
+        assert!(flip_x(&mut app, ent, Vec2::new(-40., 0.)));
+        // stopping or moving vertically keeps facing the same way
+        assert!(flip_x(&mut app, ent, Vec2::ZERO));
+        assert!(flip_x(&mut app, ent, Vec2::new(0., 40.)));
+        assert!(!flip_x(&mut app, ent, Vec2::new(40., 0.)));
+
+        // platforms don't flip
+        let platform = AnimationController::platform(&EntityInstance::default());
+        let (mut app, ent) = controlled_entity(platform, &["idle", "move"]);
+        assert!(!flip_x(&mut app, ent, Vec2::new(-40., 0.)));
+    }
+}
@@ -0,0 +1,322 @@
+use std::{fmt, path::PathBuf};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    audio::Volume,
+    prelude::*,
+    utils::HashMap,
+};
+use bevy_ecs_ldtk::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    climbing::Climber,
+    game_flow::{GameFile, RespawnLevelEvent, RespawnWorldEvent},
+    ground_detection::LandingEvent,
+    player::{AnimationEvent, AnimationEventType, Player},
+};
+
+// audio constants
+
+/// the sound config, see [`SoundConfigLoader`]. Named so its extension is
+/// `sounds.json`, the one the loader is registered for
+const SOUND_CONFIG: &str = "game.sounds.json";
+
+/// Level field with the music to loop while the player is in the level. Paths
+/// are relative to the LDtk project file. Levels without it are silent.
+pub(crate) const MUSIC_FIELD: &str = "music";
+
+/// Something that happened in the game that can make a sound
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameSound {
+    Jump,
+    Land,
+    Climb,
+    SpikeDeath,
+    Respawn,
+    Pickup,
+}
+
+/// send this to play a sound effect
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub struct PlaySoundEvent(pub GameSound);
+
+/// Every sound requested this frame & the music that should be playing.
+/// Sounds are recorded even if they have no clip or audio output, so tests can
+/// check for them without a sound card.
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+pub struct RequestedSounds {
+    pub sounds: Vec<GameSound>,
+    /// path of the current level's music, None if it's silent
+    pub music: Option<PathBuf>,
+}
+
+// SOUND CONFIG
+
+/// Which clip to play for each [`GameSound`] & how loud to play them.
+///
+/// Loaded from `assets/game.sounds.json`. Clip paths are relative to the assets
+/// folder, & sounds without a clip are silent:
+///
+/// ```json
+/// {
+///   "sounds": {
+///     "jump": "audio/jump.ogg",
+///     "spike_death": "audio/spike_death.ogg"
+///   },
+///   "sound_volume": 0.8,
+///   "music_volume": 0.5,
+///   "crossfade_secs": 1.5
+/// }
+/// ```
+#[derive(Asset, TypePath, Clone, PartialEq, Debug, Deserialize)]
+pub struct SoundConfig {
+    #[serde(default)]
+    pub sounds: HashMap<GameSound, String>,
+    #[serde(default = "default_volume")]
+    pub sound_volume: f32,
+    #[serde(default = "default_volume")]
+    pub music_volume: f32,
+    /// how long the old level's music takes to fade out & the new one to
+    /// fade in
+    #[serde(default = "default_crossfade_secs")]
+    pub crossfade_secs: f32,
+}
+fn default_volume() -> f32 {
+    1.
+}
+fn default_crossfade_secs() -> f32 {
+    1.
+}
+
+#[derive(Default)]
+pub struct SoundConfigLoader;
+
+#[derive(Debug)]
+pub enum SoundConfigLoadError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+impl fmt::Display for SoundConfigLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read sound config: {err}"),
+            Self::Json(err) => write!(f, "could not parse sound config: {err}"),
+        }
+    }
+}
+impl std::error::Error for SoundConfigLoadError {}
+impl From<std::io::Error> for SoundConfigLoadError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+impl From<serde_json::Error> for SoundConfigLoadError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl AssetLoader for SoundConfigLoader {
+    type Asset = SoundConfig;
+    type Settings = ();
+    type Error = SoundConfigLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sounds.json"]
+    }
+}
+
+#[derive(Resource)]
+pub(crate) struct SoundConfigHandle(pub(crate) Handle<SoundConfig>);
+
+fn load_sound_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SoundConfigHandle(asset_server.load(SOUND_CONFIG)));
+}
+
+// SOUND EFFECTS
+
+/// turn gameplay events into sounds
+#[allow(clippy::too_many_arguments)]
+fn sounds_from_gameplay(
+    mut sound_events: EventWriter<PlaySoundEvent>,
+    mut animation_events: EventReader<AnimationEvent>,
+    mut level_respawn_events: EventReader<RespawnLevelEvent>,
+    mut world_respawn_events: EventReader<RespawnWorldEvent>,
+    mut landing_events: EventReader<LandingEvent>,
+    players: Query<(), With<Player>>,
+    climbers: Query<&Climber, (With<Player>, Changed<Climber>)>,
+    mut was_climbing: Local<bool>,
+) {
+    for animation_event in animation_events.read() {
+        if animation_event.typ == AnimationEventType::Jumping {
+            sound_events.send(PlaySoundEvent(GameSound::Jump));
+        }
+    }
+
+    if level_respawn_events.read().count() + world_respawn_events.read().count() > 0 {
+        sound_events.send(PlaySoundEvent(GameSound::Respawn));
+    }
+
+    for landing_event in landing_events.read() {
+        if players.contains(landing_event.ent) {
+            sound_events.send(PlaySoundEvent(GameSound::Land));
+        }
+    }
+
+    for climber in &climbers {
+        if climber.climbing && !*was_climbing {
+            sound_events.send(PlaySoundEvent(GameSound::Climb));
+        }
+        *was_climbing = climber.climbing;
+    }
+}
+
+fn clear_requested_sounds(mut requested_sounds: ResMut<RequestedSounds>) {
+    requested_sounds.sounds.clear();
+}
+
+/// record every requested sound & play the ones with a clip
+fn play_sounds(
+    mut commands: Commands,
+    mut sound_events: EventReader<PlaySoundEvent>,
+    mut requested_sounds: ResMut<RequestedSounds>,
+    sound_configs: Res<Assets<SoundConfig>>,
+    opt_config_handle: Option<Res<SoundConfigHandle>>,
+    opt_asset_server: Option<Res<AssetServer>>,
+) {
+    let opt_config = opt_config_handle.and_then(|handle| sound_configs.get(&handle.0));
+    for PlaySoundEvent(sound) in sound_events.read() {
+        requested_sounds.sounds.push(*sound);
+
+        let (Some(config), Some(asset_server)) = (opt_config, opt_asset_server.as_ref()) else {
+            continue;
+        };
+        if let Some(path) = config.sounds.get(sound) {
+            commands.spawn((
+                AudioPlayer::new(asset_server.load(path)),
+                PlaybackSettings::DESPAWN.with_volume(Volume::new(config.sound_volume)),
+            ));
+        }
+    }
+}
+
+// MUSIC
+
+/// Background music for a level. Fades in when spawned & fades out once the
+/// player leaves the level, then despawns
+#[derive(Component, Clone, PartialEq, Debug)]
+struct Music {
+    path: PathBuf,
+    fading_out: bool,
+}
+
+/// find the music for the selected level & crossfade to it if it's changed
+#[allow(clippy::too_many_arguments)]
+fn select_level_music(
+    mut commands: Commands,
+    level_selection: Res<LevelSelection>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+    game_file: Res<GameFile>,
+    mut requested_sounds: ResMut<RequestedSounds>,
+    mut music_query: Query<&mut Music>,
+    opt_asset_server: Option<Res<AssetServer>>,
+) {
+    let Some(level) = ldtk_projects
+        .get_single()
+        .ok()
+        .and_then(|handle| ldtk_project_assets.get(handle))
+        .and_then(|ldtk_project| ldtk_project.find_raw_level_by_level_selection(&level_selection))
+    else {
+        return;
+    };
+
+    // Music paths are relative to the project file
+    let project_dir = game_file.path.parent().unwrap_or(&game_file.path);
+    let music = level
+        .get_maybe_file_path_field(MUSIC_FIELD)
+        .ok()
+        .and_then(|path| path.as_ref())
+        .map(|path| project_dir.join(path));
+    if music == requested_sounds.music {
+        return;
+    }
+
+    // fade out the old music, unless it's the music we're switching back to
+    let mut already_playing = false;
+    for mut old_music in &mut music_query {
+        old_music.fading_out = Some(&old_music.path) != music.as_ref();
+        already_playing |= !old_music.fading_out;
+    }
+    if let (false, Some(path), Some(asset_server)) = (already_playing, &music, opt_asset_server) {
+        commands.spawn((
+            AudioPlayer::new(asset_server.load(path.clone())),
+            PlaybackSettings::LOOP.with_volume(Volume::ZERO),
+            Music {
+                path: path.clone(),
+                fading_out: false,
+            },
+        ));
+    }
+    requested_sounds.music = music;
+}
+
+/// fade music in & out, despawning it once it's faded out
+fn crossfade_music(
+    mut commands: Commands,
+    time: Res<Time>,
+    sound_configs: Res<Assets<SoundConfig>>,
+    opt_config_handle: Option<Res<SoundConfigHandle>>,
+    music_query: Query<(Entity, &Music, &AudioSink)>,
+) {
+    let Some(config) = opt_config_handle.and_then(|handle| sound_configs.get(&handle.0)) else {
+        return;
+    };
+    let step = config.music_volume * time.delta_secs() / config.crossfade_secs.max(f32::EPSILON);
+
+    for (music_ent, music, sink) in &music_query {
+        if music.fading_out {
+            let volume = (sink.volume() - step).max(0.);
+            sink.set_volume(volume);
+            if volume <= 0. {
+                commands.entity(music_ent).despawn_recursive();
+            }
+        } else if sink.volume() < config.music_volume {
+            sink.set_volume((sink.volume() + step).min(config.music_volume));
+        }
+    }
+}
+
+/// plays sound effects for gameplay events & per level music
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<SoundConfig>()
+            .init_asset_loader::<SoundConfigLoader>()
+            .add_event::<PlaySoundEvent>()
+            .init_resource::<RequestedSounds>()
+            .add_systems(Startup, load_sound_config)
+            .add_systems(First, clear_requested_sounds)
+            .add_systems(
+                Update,
+                (
+                    (sounds_from_gameplay, play_sounds).chain(),
+                    (select_level_music, crossfade_music).chain(),
+                ),
+            );
+    }
+}
@@ -0,0 +1,90 @@
+//! Checks LDtk projects for mistakes the game would only hit at runtime.
+//! Exits with 1 if anything was found, so it can run in CI.
+
+use std::{env, path::Path, process};
+
+use bean_platformer::lint::{lint_project, load_project};
+
+const USAGE: &str = "usage: frijolero-lint <project.ldtk>...";
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    process::exit(run(&paths));
+}
+
+/// lint the projects, returning the exit code: 0 if they're fine, 1 if there
+/// were issues & 2 if they couldn't be linted
+fn run(paths: &[String]) -> i32 {
+    if paths.is_empty() || paths.iter().any(|path| path == "-h" || path == "--help") {
+        eprintln!("{USAGE}");
+        return 2;
+    }
+
+    let mut issue_count = 0;
+    for path in paths {
+        let (ldtk_json, levels, registry) = match load_project(Path::new(path)) {
+            Ok(project) => project,
+            Err(err) => {
+                eprintln!("{err}");
+                return 2;
+            }
+        };
+        for issue in lint_project(&ldtk_json, &levels, &registry) {
+            println!("{path}: {issue}");
+            issue_count += 1;
+        }
+    }
+
+    match issue_count {
+        0 => println!("no issues in {} project(s)", paths.len()),
+        1 => println!("1 issue"),
+        _ => println!("{issue_count} issues"),
+    }
+    if issue_count > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use bevy_ecs_ldtk::ldtk::{LdtkJson, Level};
+
+    use super::*;
+
+    const FIXTURE: &str = "assets/tests/fixture.ldtk";
+
+    #[test]
+    fn clean_projects_pass() {
+        assert_eq!(run(&[FIXTURE.to_string()]), 0);
+    }
+
+    #[test]
+    fn projects_with_issues_fail() {
+        // a level with no Player_Respawn
+        let ldtk_json = LdtkJson {
+            levels: vec![Level {
+                identifier: "Level_0".to_string(),
+                layer_instances: Some(Vec::new()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let dir = env::temp_dir().join(format!("frijolero-lint-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("broken.ldtk");
+        fs::write(&path, serde_json::to_vec(&ldtk_json).unwrap()).unwrap();
+        let exit_code = run(&[path.to_string_lossy().into_owned()]);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(exit_code, 1);
+    }
+
+    #[test]
+    fn projects_that_cant_be_read_are_errors() {
+        assert_eq!(run(&["missing.ldtk".to_string()]), 2);
+        assert_eq!(run(&[]), 2);
+    }
+}
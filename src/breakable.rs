@@ -0,0 +1,342 @@
+//! Walls that can be broken. Anything that breaks things, like a ground pound,
+//! sends a [`BreakBlocksEvent`] with the area it hit. Broken blocks are
+//! removed from their tilemaps, & the walls they were merged with are merged
+//! again by [`crate::walls::rebuild_wall_collision`].
+//!
+//! Broken blocks come back when their level respawns, unless they're flagged
+//! permanent. Each broken block's flag is kept in [`BrokenBlocks`] & saved
+//! with the rest of the save data by [`crate::save`]. A block's flagged
+//! permanent when whatever broke it says so, or when its IntGrid value is
+//! `permanent` in the [`crate::registry::Registry`].
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_ecs_ldtk::{prelude::*, utils::grid_coords_to_translation};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::{
+    game_flow::{RespawnLevelEvent, RespawnWorldEvent},
+    level_data,
+    registry::{IntCellKind, Registry, RegistryAppExt},
+    walls::Wall,
+};
+
+/// tag for walls that can be broken
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Breakable;
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct BreakableBundle {
+    wall: Wall,
+    breakable: Breakable,
+    #[from_int_grid_cell]
+    int_grid_cell: IntGridCell,
+}
+
+/// break every breakable block touching this area, in world coordinates
+#[derive(Event, Copy, Clone, PartialEq, Debug)]
+pub struct BreakBlocksEvent {
+    pub area: Rect,
+    /// flag the blocks permanent, whatever their IntGrid value
+    pub permanent: bool,
+}
+
+/// where a broken block was
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BlockLocation {
+    pub level_iid: String,
+    /// the identifier of the IntGrid layer it's in
+    pub layer: String,
+    pub grid_coords: GridCoords,
+}
+
+/// Every broken block, & whether it's flagged permanent. This is the save
+/// data's state. Broken blocks are removed again whenever their level spawns,
+/// non-permanent ones are forgotten when their level respawns
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+pub struct BrokenBlocks(pub HashMap<BlockLocation, bool>);
+
+/// Remove a block & the tiles drawn over it from their tilemaps, & despawn
+/// them. Auto-tiles stacked on top of each other are spawned in extra tilemaps
+/// for the same layer, & AutoLayers built from the block's layer have tilemaps
+/// of their own
+fn remove_block(
+    commands: &mut Commands,
+    ldtk_project: Option<&LdtkProject>,
+    tile_pos: &TilePos,
+    layer: &LayerMetadata,
+    level_children: &Children,
+    tilemaps: &mut Query<(&Parent, &LayerMetadata, &mut TileStorage)>,
+) {
+    let auto_source = |layer_def_uid| {
+        ldtk_project?
+            .json_data()
+            .defs
+            .layers
+            .iter()
+            .find(|layer_def| layer_def.uid == layer_def_uid)?
+            .auto_source_layer_def_uid
+    };
+    for &child in level_children {
+        let Ok((_, tilemap_layer, mut tile_storage)) = tilemaps.get_mut(child) else {
+            continue;
+        };
+        let drawn_over = tilemap_layer.iid == layer.iid
+            || auto_source(tilemap_layer.layer_def_uid) == Some(layer.layer_def_uid);
+        if !drawn_over || tilemap_layer.grid_size != layer.grid_size {
+            continue;
+        }
+        if let Some(tile) = tile_storage.checked_get(tile_pos) {
+            tile_storage.checked_remove(tile_pos);
+            commands.entity(tile).despawn_recursive();
+        }
+    }
+}
+
+/// break the blocks hit by [`BreakBlocksEvent`]s & remember them
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn break_blocks(
+    mut commands: Commands,
+    registry: Res<Registry>,
+    mut broken_blocks: ResMut<BrokenBlocks>,
+    mut break_events: EventReader<BreakBlocksEvent>,
+    blocks: Query<(Entity, &GridCoords, &TilePos, &IntGridCell, &Parent), With<Breakable>>,
+    mut layers: Query<(&Parent, &LayerMetadata, &mut TileStorage)>,
+    levels: Query<(&LevelIid, &GlobalTransform, &Children)>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+) {
+    let ldtk_project = level_data::loaded_project(&ldtk_projects, &ldtk_project_assets);
+    for event in break_events.read() {
+        for (block, &grid_coords, tile_pos, int_grid_cell, parent) in &blocks {
+            let Ok((layer_parent, layer_metadata, tile_storage)) = layers.get(parent.get()) else {
+                continue;
+            };
+            let Ok((level_iid, level_transform, level_children)) = levels.get(layer_parent.get())
+            else {
+                continue;
+            };
+            // a block can only be broken once
+            if tile_storage.get(tile_pos) != Some(block) {
+                continue;
+            }
+
+            let grid_size = layer_metadata.grid_size;
+            let center = level_transform
+                .transform_point(
+                    grid_coords_to_translation(grid_coords, IVec2::splat(grid_size)).extend(0.),
+                )
+                .truncate();
+            let block_area = Rect::from_center_size(center, Vec2::splat(grid_size as f32));
+            if event.area.intersect(block_area).is_empty() {
+                continue;
+            }
+
+            let layer_metadata = layer_metadata.clone();
+            remove_block(
+                &mut commands,
+                ldtk_project,
+                tile_pos,
+                &layer_metadata,
+                level_children,
+                &mut layers,
+            );
+            let location = BlockLocation {
+                level_iid: level_iid.get().clone(),
+                layer: layer_metadata.identifier,
+                grid_coords,
+            };
+            let permanent = event.permanent || registry.permanent(int_grid_cell.value);
+            broken_blocks.0.insert(location, permanent);
+        }
+    }
+}
+
+/// remove newly spawned blocks that have already been broken
+#[allow(clippy::type_complexity)]
+fn remove_broken_blocks(
+    mut commands: Commands,
+    broken_blocks: Res<BrokenBlocks>,
+    blocks: Query<(&GridCoords, &TilePos, &Parent), Added<Breakable>>,
+    mut layers: Query<(&Parent, &LayerMetadata, &mut TileStorage)>,
+    levels: Query<(&LevelIid, &Children)>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+) {
+    if broken_blocks.0.is_empty() {
+        return;
+    }
+    let ldtk_project = level_data::loaded_project(&ldtk_projects, &ldtk_project_assets);
+    for (&grid_coords, tile_pos, parent) in &blocks {
+        let Ok((layer_parent, layer_metadata, _)) = layers.get(parent.get()) else {
+            continue;
+        };
+        let Ok((level_iid, level_children)) = levels.get(layer_parent.get()) else {
+            continue;
+        };
+        let location = BlockLocation {
+            level_iid: level_iid.get().clone(),
+            layer: layer_metadata.identifier.clone(),
+            grid_coords,
+        };
+        if broken_blocks.0.contains_key(&location) {
+            let layer_metadata = layer_metadata.clone();
+            remove_block(
+                &mut commands,
+                ldtk_project,
+                tile_pos,
+                &layer_metadata,
+                level_children,
+                &mut layers,
+            );
+        }
+    }
+}
+
+/// forget the non-permanent broken blocks of the levels that are respawning,
+/// so they come back
+fn reset_broken_blocks(
+    mut broken_blocks: ResMut<BrokenBlocks>,
+    level_selection: Res<LevelSelection>,
+    mut level_respawn_events: EventReader<RespawnLevelEvent>,
+    mut world_respawn_events: EventReader<RespawnWorldEvent>,
+) {
+    if !world_respawn_events.is_empty() {
+        world_respawn_events.clear();
+        level_respawn_events.clear();
+        broken_blocks.0.retain(|_, permanent| *permanent);
+    } else if !level_respawn_events.is_empty() {
+        level_respawn_events.clear();
+        if let LevelSelection::Iid(level_iid) = level_selection.as_ref() {
+            broken_blocks
+                .0
+                .retain(|location, permanent| *permanent || location.level_iid != *level_iid.get());
+        }
+    }
+}
+
+pub struct BreakablePlugin;
+
+impl Plugin for BreakablePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BrokenBlocks>()
+            .add_event::<BreakBlocksEvent>()
+            .register_int_cell_kind::<BreakableBundle>(IntCellKind::Breakable)
+            .add_systems(
+                Update,
+                (reset_broken_blocks, break_blocks, remove_broken_blocks).chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{registry::IntCellMapping, test_harness::TestGame};
+
+    /// how many tiles are at this position in the level's tilemaps
+    fn tiles_at(game: &mut TestGame, level: &LevelIid, tile_pos: TilePos) -> usize {
+        let world = game.app.world_mut();
+        let mut levels = world.query::<&LevelIid>();
+        world
+            .query::<(&Parent, &TileStorage)>()
+            .iter(world)
+            .filter(|(parent, tile_storage)| {
+                levels.get(world, parent.get()) == Ok(level)
+                    && tile_storage.checked_get(&tile_pos).is_some()
+            })
+            .count()
+    }
+
+    /// Start on the fixture with every wall breakable, permanent or not, break
+    /// the block in the selected level furthest from the player, flagging it
+    /// permanent or not, & respawn the level. Returns
+    /// whether the block's collider was there & how many tiles were drawn
+    /// where it is, before breaking it, after breaking it & after respawning
+    fn break_a_block_then_respawn(
+        permanent_value: bool,
+        permanent_break: bool,
+    ) -> [(bool, usize); 3] {
+        let mut registry = Registry::default();
+        for value in 1..=3 {
+            registry.int_cells.insert(
+                value,
+                IntCellMapping {
+                    kind: IntCellKind::Breakable,
+                    collider: None,
+                    surface: default(),
+                    permanent: permanent_value,
+                },
+            );
+        }
+        let mut game = TestGame::with_registry("tests/fixture.ldtk", registry);
+        game.tick(30);
+
+        let LevelSelection::Iid(selected_level) = game.level_selection() else {
+            panic!("a level should be selected by its iid");
+        };
+        let player = game.player_position();
+        let world = game.app.world_mut();
+        let mut layers = world.query::<(&Parent, &LayerMetadata)>();
+        let mut levels = world.query::<(&LevelIid, &GlobalTransform)>();
+        let (block, tile_pos) = world
+            .query_filtered::<(&GridCoords, &TilePos, &Parent), With<Breakable>>()
+            .iter(world)
+            .filter_map(|(&grid_coords, &tile_pos, parent)| {
+                let (layer_parent, layer_metadata) = layers.get(world, parent.get()).ok()?;
+                let (level_iid, level_transform) = levels.get(world, layer_parent.get()).ok()?;
+                let translation =
+                    grid_coords_to_translation(grid_coords, IVec2::splat(layer_metadata.grid_size));
+                let center = level_transform
+                    .transform_point(translation.extend(0.))
+                    .truncate();
+                (*level_iid == selected_level).then_some((center, tile_pos))
+            })
+            .max_by(|(a, _), (b, _)| a.distance(player).total_cmp(&b.distance(player)))
+            .expect("the selected level should have walls");
+        let state = |game: &mut TestGame| {
+            (
+                game.wall_collider_at(block),
+                tiles_at(game, &selected_level, tile_pos),
+            )
+        };
+
+        let before = state(&mut game);
+        game.app.world_mut().send_event(BreakBlocksEvent {
+            area: Rect::from_center_size(block, Vec2::ONE),
+            permanent: permanent_break,
+        });
+        game.tick(2);
+        let broken = state(&mut game);
+        game.app
+            .world_mut()
+            .send_event(RespawnLevelEvent::RespawnLevelEvent);
+        game.tick(10);
+        [before, broken, state(&mut game)]
+    }
+
+    #[test]
+    fn broken_blocks_come_back_when_their_level_respawns() {
+        // the block's drawn by the fixture's AutoLayer, as well as its IntGrid
+        // layer
+        assert_eq!(
+            break_a_block_then_respawn(false, false),
+            [(true, 2), (false, 0), (true, 2)]
+        );
+    }
+
+    #[test]
+    fn permanent_blocks_stay_broken() {
+        assert_eq!(
+            break_a_block_then_respawn(true, false),
+            [(true, 2), (false, 0), (false, 0)]
+        );
+    }
+
+    #[test]
+    fn blocks_broken_for_good_stay_broken() {
+        assert_eq!(
+            break_a_block_then_respawn(false, true),
+            [(true, 2), (false, 0), (false, 0)]
+        );
+    }
+}
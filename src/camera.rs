@@ -1,135 +1,311 @@
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
-use crate::player::Player;
+use crate::{
+    camera_shake::CameraShake,
+    camera_zone::CameraZone,
+    ground_detection::GroundDetection,
+    level_data::{self, LevelDataErrors},
+    player::{AnimationEvent, AnimationEventType, Player, RunningDirection},
+    room_transition::RoomTransition,
+};
 
-const ASPECT_RATIO_HEIGHT: f32 = 9.;
-const ASPECT_RATIO_WIDTH: f32 = 16.;
-const ASPECT_RATIO: f32 = ASPECT_RATIO_WIDTH / ASPECT_RATIO_HEIGHT;
+/// configures the size & shape of the camera's view
+#[derive(Resource, Clone, Debug)]
+pub struct CameraConfig {
+    /// the width & height parts of the aspect ratio, ie 16:9. windows with a
+    /// different aspect ratio are letterboxed
+    pub aspect_ratio: UVec2,
+    /// how many pixels of the level the camera shows vertically
+    pub view_height: u32,
+    /// render the game at its native resolution, then scale it up by a whole
+    /// number to fit the window. keeps pixels square & crisp
+    pub pixel_perfect: bool,
+}
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            aspect_ratio: UVec2::new(16, 9),
+            view_height: 200,
+            pixel_perfect: false,
+        }
+    }
+}
+impl CameraConfig {
+    pub fn aspect_ratio(&self) -> f32 {
+        self.aspect_ratio.x as f32 / self.aspect_ratio.y as f32
+    }
+
+    /// the native resolution of the game, in pixels
+    pub fn resolution(&self) -> UVec2 {
+        UVec2::new(
+            (self.view_height as f32 * self.aspect_ratio()).round() as u32,
+            self.view_height,
+        )
+    }
+}
 
-const CAMERA_HEIGHT: f32 = 200.0;
-const CAMERA_WIDTH: f32 = CAMERA_HEIGHT * ASPECT_RATIO;
+/// configures how the camera follows the player, and tracks the state needed
+/// to do so smoothly
+#[derive(Clone, Debug, Component)]
+pub struct CameraController {
+    /// how quickly the camera catches up to where it wants to be. higher is
+    /// snappier
+    pub damping: f32,
+    /// half the size of the box the player can move around in without moving
+    /// the camera
+    pub deadzone: Vec2,
+    /// how far the camera looks ahead of the player in the direction they're
+    /// facing
+    pub look_ahead: f32,
+    /// how quickly the look ahead swings around when the player turns
+    pub look_ahead_damping: f32,
 
-const CAMERA_HEIGHT_HALF: f32 = CAMERA_HEIGHT / 2.;
-const CAMERA_WIDTH_HALF: f32 = CAMERA_WIDTH / 2.;
+    // the point the camera is centered on, before look ahead
+    focus: Option<Vec2>,
+    // where the camera is, before any shake is applied
+    translation: Option<Vec2>,
+    // 1 for right, -1 for left
+    facing: f32,
+    current_look_ahead: f32,
+}
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            damping: 8.,
+            deadzone: Vec2::new(16., 24.),
+            look_ahead: 32.,
+            look_ahead_damping: 3.,
+            focus: None,
+            translation: None,
+            facing: 1.,
+            current_look_ahead: 0.,
+        }
+    }
+}
+impl CameraController {
+    /// move the focus point to keep the player inside the deadzone & get the
+    /// point the camera should center on
+    fn follow(&mut self, player_pos: Vec2, on_ground: bool, delta_secs: f32) -> Vec2 {
+        let mut focus = self.focus.unwrap_or(player_pos);
 
-#[allow(clippy::type_complexity)]
+        // horizontally, only move the camera once the player leaves the
+        // deadzone
+        focus.x = focus.x.clamp(
+            player_pos.x - self.deadzone.x,
+            player_pos.x + self.deadzone.x,
+        );
+
+        // vertically, only re-center when the player lands. this keeps the
+        // camera from bobbing up & down with every jump
+        if on_ground {
+            focus.y = player_pos.y;
+        } else {
+            focus.y = focus.y.clamp(
+                player_pos.y - self.deadzone.y,
+                player_pos.y + self.deadzone.y,
+            );
+        }
+        self.focus = Some(focus);
+
+        // swing the look ahead towards the direction the player is facing
+        self.current_look_ahead = self.current_look_ahead.lerp(
+            self.facing * self.look_ahead,
+            damping_factor(self.look_ahead_damping, delta_secs),
+        );
+
+        focus + Vec2::new(self.current_look_ahead, 0.)
+    }
+}
+
+/// what the camera can see: the bounds it has to stay inside of, how big the
+/// view is and the point it's trying to center on
+#[derive(Clone, Copy, Debug)]
+pub struct CameraView {
+    pub bounds: Rect,
+    pub view_size: Vec2,
+    pub focus: Vec2,
+}
+
+/// get the bottom-left corner of a camera of the provided size, centered on
+/// the focus point but kept inside the bounds. If the bounds are smaller than
+/// the view on an axis, center the view on the bounds instead
+fn fit_inside(focus: Vec2, view_size: Vec2, bounds: Rect) -> Vec2 {
+    let fit_axis = |focus: f32, view: f32, min: f32, max: f32| {
+        if max - min < view {
+            (min + max - view) / 2.
+        } else {
+            (focus - view / 2.).clamp(min, max - view)
+        }
+    };
+    Vec2::new(
+        fit_axis(focus.x, view_size.x, bounds.min.x, bounds.max.x),
+        fit_axis(focus.y, view_size.y, bounds.min.y, bounds.max.y),
+    )
+}
+
+/// frame-rate independent lerp factor for the provided damping
+fn damping_factor(damping: f32, delta_secs: f32) -> f32 {
+    1. - (-damping * delta_secs).exp()
+}
+
+/// point the camera's look ahead in the direction the player is running
+pub fn update_camera_facing(
+    mut animation_events: EventReader<AnimationEvent>,
+    player_query: Query<(), With<Player>>,
+    mut camera_query: Query<&mut CameraController>,
+) {
+    for animation_event in animation_events.read() {
+        if !player_query.contains(animation_event.ent) {
+            continue;
+        }
+        let facing = match animation_event.typ {
+            AnimationEventType::Running(RunningDirection::Right) => 1.,
+            AnimationEventType::Running(RunningDirection::Left) => -1.,
+            _ => continue,
+        };
+        for mut camera_controller in &mut camera_query {
+            camera_controller.facing = facing;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 pub fn camera_fit_inside_current_level(
     mut camera_query: Query<
         (
             &mut bevy::render::camera::OrthographicProjection,
             &mut Transform,
+            &mut CameraController,
         ),
         Without<Player>,
     >,
-    player_query: Query<&Transform, With<Player>>,
+    player_query: Query<(&Transform, &GroundDetection), With<Player>>,
     level_query: Query<(&Transform, &LevelIid), (Without<OrthographicProjection>, Without<Player>)>,
     ldtk_projects: Query<&LdtkProjectHandle>,
     level_selection: Res<LevelSelection>,
     ldtk_project_assets: Res<Assets<LdtkProject>>,
+    room_transition: Res<RoomTransition>,
+    camera_shake: Res<CameraShake>,
+    camera_zones: Query<(&CameraZone, &GlobalTransform)>,
+    camera_config: Res<CameraConfig>,
+    time: Res<Time>,
+    mut level_data_errors: ResMut<LevelDataErrors>,
 ) {
-    if let Ok(Transform {
-        translation: player_translation,
-        ..
-    }) = player_query.get_single()
-    {
-        let player_translation = *player_translation;
+    let Some(ldtk_project) = level_data::loaded_project(&ldtk_projects, &ldtk_project_assets)
+    else {
+        return;
+    };
+    if let Ok((player_transform, ground_detection)) = player_query.get_single() {
+        let Ok((mut orthographic_projection, mut camera_transform, mut camera_controller)) =
+            camera_query.get_single_mut()
+        else {
+            return;
+        };
 
-        let (mut orthographic_projection, mut camera_transform) = camera_query.single_mut();
+        // Rather than following the player directly, follow the controller's
+        // focus point
+        let focus = camera_controller.follow(
+            player_transform.translation.truncate(),
+            ground_detection.on_ground(),
+            time.delta_secs(),
+        );
 
         for (level_transform, level_iid) in &level_query {
-            let ldtk_project = ldtk_project_assets
-                .get(ldtk_projects.single())
-                .expect("Project should be loaded if level has spawned");
-
-            let level = ldtk_project
-                .get_raw_level_by_iid(&level_iid.to_string())
-                .expect("Spawned level should exist in LDtk project");
+            let level = match level_data::raw_level(ldtk_project, level_iid) {
+                Ok(level) => level,
+                Err(err) => {
+                    level_data::report(&mut level_data_errors, err);
+                    continue;
+                }
+            };
 
             if level_selection.is_match(&LevelIndices::default(), level) {
                 let level_width = level.px_wid as f32;
                 let level_height = level.px_hei as f32;
                 orthographic_projection.viewport_origin = Vec2::ZERO;
 
-                if level_width < CAMERA_WIDTH {
-                    let width = (level_width / ASPECT_RATIO_WIDTH).round() * ASPECT_RATIO_WIDTH;
-                    let height = width / ASPECT_RATIO;
-                    orthographic_projection.scaling_mode =
-                        bevy::render::camera::ScalingMode::Fixed { width, height };
-
-                    // Convert the wordly player coords into level coords,
-                    // fit the camera to the level, then convert back to world
-                    // coords
-                    camera_transform.translation.y =
-                        (player_translation.y - level_transform.translation.y - height / 2.)
-                            .clamp(0., level_height - height)
-                            + level_transform.translation.y;
-                    camera_transform.translation.x = level_transform.translation.x;
-                } else if level_height < CAMERA_HEIGHT {
-                    let height = (level_height / ASPECT_RATIO_HEIGHT).round() * ASPECT_RATIO_HEIGHT;
-                    let width = height * ASPECT_RATIO;
-                    orthographic_projection.scaling_mode =
-                        bevy::render::camera::ScalingMode::Fixed { width, height };
-
-                    // Convert the wordly player coords into level coords,
-                    // fit the camera to the level, then convert back to world
-                    // coords
-                    camera_transform.translation.x =
-                        (player_translation.x - level_transform.translation.x - width / 2.)
-                            .clamp(0., level_width - width)
-                            + level_transform.translation.x;
-
-                    camera_transform.translation.y = level_transform.translation.y;
+                // Pick how much of the level the camera should show. If the
+                // level is smaller than the view in either dimension, zoom
+                // in until the view fits inside the level in both
+                let default_view_size = camera_config.resolution().as_vec2();
+                let fit_scale = (Vec2::new(level_width, level_height) / default_view_size)
+                    .min_element()
+                    .min(1.);
+                let view_size = default_view_size * fit_scale;
+
+                let level_min = level_transform.translation.truncate();
+                let level_bounds =
+                    Rect::from_corners(level_min, level_min + Vec2::new(level_width, level_height));
+
+                // Let any camera zones the player is in override the bounds,
+                // zoom & focus
+                let CameraView {
+                    bounds,
+                    view_size,
+                    focus,
+                } = camera_zones.iter().fold(
+                    CameraView {
+                        bounds: level_bounds,
+                        view_size,
+                        focus,
+                    },
+                    |view, (camera_zone, zone_transform)| {
+                        camera_zone.apply(view, zone_transform.translation().truncate())
+                    },
+                );
+
+                orthographic_projection.scaling_mode = bevy::render::camera::ScalingMode::Fixed {
+                    width: view_size.x,
+                    height: view_size.y,
+                };
+
+                // Make the camera follow the focus point, fitting the camera
+                // inside the bounds
+                let mut camera_target = fit_inside(focus, view_size, bounds);
+
+                // If we just moved between levels, pan towards the target.
+                // Otherwise, ease towards it
+                if let RoomTransition::Transitioning(transitioning) = room_transition.as_ref() {
+                    camera_target = transitioning.camera_translation(camera_target);
                 } else {
-                    orthographic_projection.scaling_mode =
-                        bevy::render::camera::ScalingMode::Fixed {
-                            width: CAMERA_WIDTH,
-                            height: CAMERA_HEIGHT,
-                        };
-
-                    // Convert the wordly player coords  into level
-                    let player_x_in_level = player_translation.x - level_transform.translation.x;
-                    let player_y_in_level = player_translation.y - level_transform.translation.y;
-
-                    // Make the camera follow the player, fitting camera inside level
-                    let camera_x_in_level =
-                        player_x_in_level.clamp(CAMERA_WIDTH_HALF, level_width - CAMERA_WIDTH_HALF);
-                    let camera_y_in_level = player_y_in_level
-                        .clamp(CAMERA_HEIGHT_HALF, level_height - CAMERA_HEIGHT_HALF);
-
-                    // Center the camera on the player, & convert level coords back
-                    // into world coords
-                    camera_transform.translation.x =
-                        camera_x_in_level - CAMERA_WIDTH_HALF + level_transform.translation.x;
-                    camera_transform.translation.y =
-                        camera_y_in_level - CAMERA_HEIGHT_HALF + level_transform.translation.y;
+                    camera_target = camera_controller
+                        .translation
+                        .unwrap_or(camera_transform.translation.truncate())
+                        .lerp(
+                            camera_target,
+                            damping_factor(camera_controller.damping, time.delta_secs()),
+                        );
                 }
+                camera_controller.translation = Some(camera_target);
+
+                // Layer the shake on top, keeping the camera inside the
+                // bounds. If the camera is already outside of the bounds (ie
+                // mid transition), don't let the shake push it further out
+                let camera_max = bounds.min + (bounds.size() - view_size).max(Vec2::ZERO);
+                let mut shaken = (camera_target + camera_shake.offset())
+                    .clamp(bounds.min.min(camera_target), camera_max.max(camera_target));
 
-                // if level_ratio > ASPECT_RATIO {
-                //     // level is wider than the screen
-                //     let height = (level.px_hei as f32 / 9.).round() * 9.;
-                //     let width = height * ASPECT_RATIO;
-                //     orthographic_projection.scaling_mode =
-                //         bevy::render::camera::ScalingMode::Fixed { width, height };
-                //     camera_transform.translation.x =
-                //         (player_translation.x - level_transform.translation.x - width / 2.)
-                //             .clamp(0., level_width - width);
-                //     camera_transform.translation.y = 0.;
-                // } else {
-                //     // level is taller than the screen
-                //     let width = (level.px_wid as f32 / 16.).round() * 16.;
-                //     let height = width / ASPECT_RATIO;
-                //     orthographic_projection.scaling_mode =
-                //         bevy::render::camera::ScalingMode::Fixed { width, height };
-                //     camera_transform.translation.y =
-                //         (player_translation.y - level_transform.translation.y - height / 2.)
-                //             .clamp(0., level.px_hei as f32 - height);
-                //     camera_transform.translation.x = 0.;
-                // }
-                //
-                // camera_transform.translation.x += level_transform.translation.x;
-                // camera_transform.translation.y += level_transform.translation.y;
+                // When rendering pixel perfect, keep the camera on whole
+                // pixels so sprites don't shimmer as it moves
+                if camera_config.pixel_perfect {
+                    shaken = shaken.round();
+                }
+                camera_transform.translation.x = shaken.x;
+                camera_transform.translation.y = shaken.y;
             }
         }
     }
 }
+
+/// handles fitting the camera to the current level & following the player
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraConfig>().add_systems(
+            Update,
+            (update_camera_facing, camera_fit_inside_current_level).chain(),
+        );
+    }
+}
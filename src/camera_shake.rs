@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+// camera shake constants
+
+/// trauma added when the player hits something that hurts
+pub const DAMAGE_TRAUMA: f32 = 0.5;
+/// trauma added when the player lands after a long fall
+pub const HEAVY_LANDING_TRAUMA: f32 = 0.3;
+/// trauma added when a ground pound lands
+pub const GROUND_POUND_TRAUMA: f32 = 0.4;
+
+/// send this to shake the camera. trauma is added to the current trauma, and
+/// the total is capped at 1
+#[derive(Event, PartialEq, Debug, Copy, Clone)]
+pub struct CameraShakeEvent {
+    pub trauma: f32,
+}
+impl CameraShakeEvent {
+    pub fn new(trauma: f32) -> Self {
+        CameraShakeEvent { trauma }
+    }
+    pub fn damage() -> Self {
+        Self::new(DAMAGE_TRAUMA)
+    }
+    pub fn heavy_landing() -> Self {
+        Self::new(HEAVY_LANDING_TRAUMA)
+    }
+    pub fn ground_pound() -> Self {
+        Self::new(GROUND_POUND_TRAUMA)
+    }
+}
+
+/// trauma-based camera shake. the camera is offset by an amount proportional
+/// to trauma squared, so small hits give a small shake while big hits give a
+/// big one. trauma decays linearly over time
+#[derive(Resource, Debug, Clone)]
+pub struct CameraShake {
+    /// how shaken the camera currently is, 0 to 1
+    pub trauma: f32,
+    /// how far the camera can be offset at max trauma
+    pub max_offset: Vec2,
+    /// how much trauma is removed per second
+    pub decay: f32,
+    /// how fast the shake moves around
+    pub frequency: f32,
+    /// picks which shake pattern plays. Set from the command line with
+    /// `--seed`
+    pub seed: u32,
+
+    // drives the noise, only advances while shaking
+    time: f32,
+}
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.,
+            max_offset: Vec2::new(8., 6.),
+            decay: 1.5,
+            frequency: 25.,
+            seed: 0,
+            time: 0.,
+        }
+    }
+}
+impl CameraShake {
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0., 1.);
+    }
+
+    /// get how far the camera should be offset this frame
+    pub fn offset(&self) -> Vec2 {
+        if self.trauma <= 0. {
+            return Vec2::ZERO;
+        }
+        let shake = self.trauma * self.trauma;
+        let t = self.time * self.frequency;
+        // sample a different part of the noise for each axis
+        Vec2::new(noise(self.seed, t), noise(self.seed, t + 1000.)) * self.max_offset * shake
+    }
+}
+
+/// smooth 1D value noise in [-1, 1], different for every seed
+fn noise(seed: u32, t: f32) -> f32 {
+    // hash an integer to a pseudo-random value in [-1, 1]
+    let hash = |n: i32| {
+        let mut x = (n as u32) ^ seed.wrapping_mul(0x9e37_79b9);
+        x = (x ^ 61) ^ (x >> 16);
+        x = x.wrapping_mul(9);
+        x ^= x >> 4;
+        x = x.wrapping_mul(0x27d4_eb2d);
+        x ^= x >> 15;
+        (x as f32 / u32::MAX as f32) * 2. - 1.
+    };
+
+    let i = t.floor();
+    let f = t - i;
+    // smoothstep between the random values at neighbouring integers
+    let blend = f * f * (3. - 2. * f);
+    let a = hash(i as i32);
+    let b = hash(i as i32 + 1);
+    a + (b - a) * blend
+}
+
+/// add trauma for every shake event
+fn receive_camera_shake_events(
+    mut shake_events: EventReader<CameraShakeEvent>,
+    mut camera_shake: ResMut<CameraShake>,
+) {
+    for shake_event in shake_events.read() {
+        camera_shake.add_trauma(shake_event.trauma);
+    }
+}
+
+/// advance the shake & decay the trauma
+fn decay_camera_shake(time: Res<Time>, mut camera_shake: ResMut<CameraShake>) {
+    if camera_shake.trauma > 0. {
+        camera_shake.time += time.delta_secs();
+        camera_shake.trauma =
+            (camera_shake.trauma - camera_shake.decay * time.delta_secs()).max(0.);
+    } else {
+        camera_shake.time = 0.;
+    }
+}
+
+/// lets any system shake the camera by sending a CameraShakeEvent
+pub struct CameraShakePlugin;
+
+impl Plugin for CameraShakePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CameraShakeEvent>()
+            .init_resource::<CameraShake>()
+            .add_systems(
+                Update,
+                (receive_camera_shake_events, decay_camera_shake).chain(),
+            );
+    }
+}
@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{
+    camera::CameraView,
+    player::Player,
+    registry::{EntityKind, RegistryAppExt},
+};
+
+// camera zone constants
+
+/// how long it takes to fully blend into or out of a zone
+const CAMERA_ZONE_BLEND_SECS: f32 = 0.5;
+
+/// An area of a level that changes how the camera behaves while the player is
+/// inside of it. For example, a boss arena that locks the camera in place or
+/// a vertical shaft that only scrolls up & down.
+///
+/// Authored in LDtk as a resizable `Camera_Zone` entity with the fields:
+/// * `zoom` (Float, optional) - how far to zoom in, 1 is the default view
+/// * `lock_x` (Bool) - keep the camera centered on the zone horizontally
+/// * `lock_y` (Bool) - keep the camera centered on the zone vertically
+#[derive(Clone, PartialEq, Debug, Component)]
+pub struct CameraZone {
+    pub half_extents: Vec2,
+    pub zoom: f32,
+    pub lock_x: bool,
+    pub lock_y: bool,
+
+    // how much the zone is currently affecting the camera, 0 to 1
+    weight: f32,
+}
+impl Default for CameraZone {
+    fn default() -> Self {
+        Self {
+            half_extents: Vec2::ZERO,
+            zoom: 1.,
+            lock_x: false,
+            lock_y: false,
+            weight: 0.,
+        }
+    }
+}
+
+impl From<&EntityInstance> for CameraZone {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        CameraZone {
+            half_extents: Vec2::new(entity_instance.width as f32, entity_instance.height as f32)
+                / 2.,
+            zoom: entity_instance
+                .get_maybe_float_field("zoom")
+                .ok()
+                .copied()
+                .flatten()
+                .filter(|zoom| *zoom > 0.)
+                .unwrap_or(1.),
+            lock_x: entity_instance
+                .get_bool_field("lock_x")
+                .copied()
+                .unwrap_or(false),
+            lock_y: entity_instance
+                .get_bool_field("lock_y")
+                .copied()
+                .unwrap_or(false),
+            weight: 0.,
+        }
+    }
+}
+
+impl CameraZone {
+    /// blend the camera view towards this zone's settings, based on how much
+    /// the zone is currently affecting the camera
+    pub fn apply(&self, view: CameraView, zone_center: Vec2) -> CameraView {
+        if self.weight <= 0. {
+            return view;
+        }
+
+        // smoothstep, so the blend eases in & out
+        let t = self.weight * self.weight * (3. - 2. * self.weight);
+
+        let zone_bounds = Rect::from_center_half_size(zone_center, self.half_extents);
+        let bounds = Rect::from_corners(
+            view.bounds.min.lerp(zone_bounds.min, t),
+            view.bounds.max.lerp(zone_bounds.max, t),
+        );
+
+        let view_size = view.view_size / (1. + (self.zoom - 1.) * t);
+
+        let mut focus = view.focus;
+        if self.lock_x {
+            focus.x = focus.x.lerp(zone_center.x, t);
+        }
+        if self.lock_y {
+            focus.y = focus.y.lerp(zone_center.y, t);
+        }
+
+        CameraView {
+            bounds,
+            view_size,
+            focus,
+        }
+    }
+
+    fn contains(&self, zone_center: Vec2, point: Vec2) -> bool {
+        Rect::from_center_half_size(zone_center, self.half_extents).contains(point)
+    }
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct CameraZoneBundle {
+    #[from_entity_instance]
+    pub camera_zone: CameraZone,
+}
+
+/// fade zones in while the player is inside of them, and out once they leave
+fn update_camera_zone_weights(
+    time: Res<Time>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut camera_zones: Query<(&mut CameraZone, &GlobalTransform)>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_pos = player_transform.translation().truncate();
+    let step = time.delta_secs() / CAMERA_ZONE_BLEND_SECS;
+
+    for (mut camera_zone, zone_transform) in &mut camera_zones {
+        let target = if camera_zone.contains(zone_transform.translation().truncate(), player_pos) {
+            1.
+        } else {
+            0.
+        };
+        if camera_zone.weight != target {
+            camera_zone.weight = if target > camera_zone.weight {
+                (camera_zone.weight + step).min(target)
+            } else {
+                (camera_zone.weight - step).max(target)
+            };
+        }
+    }
+}
+
+/// lets levels override the camera bounds, zoom & locked axes with
+/// `Camera_Zone` entities
+pub struct CameraZonePlugin;
+
+impl Plugin for CameraZonePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_entity_kind::<CameraZoneBundle>(EntityKind::CameraZone)
+            .add_systems(Update, update_camera_zone_weights);
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use bean_platformer::{game_flow::PlayerStart, replay::ReplayMode};
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use bevy_ecs_ldtk::{ldtk::LdtkJson, prelude::*};
+
+pub const USAGE: &str = "\
+usage: bean-platformer [options] [project.ldtk]
+
+the project is relative to the assets folder, & defaults to bean_platformer.ldtk
+
+options:
+  --level <identifier|iid|index>
+                            start in this level, at its Player_Respawn
+  --spawn <x>,<y>           start at this pixel position in the start level,
+                            as shown in LDtk
+  --window <width>x<height> size of the window
+  --record <file>           record the player's inputs to a file
+  --replay <file>           replay the player's inputs from a file
+  --seed <number>           seed for the game's randomness, like the camera
+                            shake, so runs look the same
+  --headless                run without a window, exits once a replay ends
+  --list-levels             print the project's levels & exit
+  -h, --help                print this & exit";
+
+/// the command line arguments, see [`USAGE`]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Args {
+    pub project: PathBuf,
+    pub player_start: PlayerStart,
+    pub window_size: Option<UVec2>,
+    pub replay_mode: ReplayMode,
+    pub seed: Option<u32>,
+    pub headless: bool,
+    pub list_levels: bool,
+    pub help: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            project: PathBuf::from("bean_platformer.ldtk"),
+            player_start: PlayerStart::default(),
+            window_size: None,
+            replay_mode: ReplayMode::Off,
+            seed: None,
+            headless: false,
+            list_levels: false,
+            help: false,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ArgsError {
+    MissingValue(String),
+    InvalidValue { flag: String, value: String },
+    UnknownFlag(String),
+}
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingValue(flag) => write!(f, "{flag} needs a value"),
+            Self::InvalidValue { flag, value } => write!(f, "invalid value for {flag}: {value}"),
+            Self::UnknownFlag(flag) => write!(f, "unknown option {flag}"),
+        }
+    }
+}
+
+/// parse `<a><separator><b>`, e.g. `1280x720`
+fn parse_pair<T: std::str::FromStr>(value: &str, separator: char) -> Option<(T, T)> {
+    let (a, b) = value.split_once(separator)?;
+    Some((a.trim().parse().ok()?, b.trim().parse().ok()?))
+}
+
+/// LDtk iids are UUIDs & identifiers can't start with a digit, so numbers are
+/// indexes into the project's levels
+fn parse_level(value: &str) -> LevelSelection {
+    let is_iid = value.len() == 36
+        && value.chars().enumerate().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        });
+    if is_iid {
+        LevelSelection::iid(value)
+    } else if let Ok(index) = value.parse() {
+        LevelSelection::index(index)
+    } else {
+        LevelSelection::Identifier(value.to_string())
+    }
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, ArgsError> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |flag: &str| {
+                args.next()
+                    .ok_or_else(|| ArgsError::MissingValue(flag.to_string()))
+            };
+            match arg.as_str() {
+                "--level" => parsed.player_start.level = Some(parse_level(&value(&arg)?)),
+                "--spawn" => {
+                    let value = value(&arg)?;
+                    let (x, y) = parse_pair(&value, ',')
+                        .ok_or(ArgsError::InvalidValue { flag: arg, value })?;
+                    parsed.player_start.position = Some(IVec2::new(x, y));
+                }
+                "--window" => {
+                    let value = value(&arg)?;
+                    let (width, height) = parse_pair(&value, 'x')
+                        .ok_or(ArgsError::InvalidValue { flag: arg, value })?;
+                    parsed.window_size = Some(UVec2::new(width, height));
+                }
+                "--record" => parsed.replay_mode = ReplayMode::Record(value(&arg)?.into()),
+                "--replay" => parsed.replay_mode = ReplayMode::Replay(value(&arg)?.into()),
+                "--seed" => {
+                    let value = value(&arg)?;
+                    let seed = value
+                        .parse()
+                        .map_err(|_| ArgsError::InvalidValue { flag: arg, value })?;
+                    parsed.seed = Some(seed);
+                }
+                "--headless" => parsed.headless = true,
+                "--list-levels" => parsed.list_levels = true,
+                "-h" | "--help" => parsed.help = true,
+                flag if flag.starts_with('-') => return Err(ArgsError::UnknownFlag(arg)),
+                _ => parsed.project = arg.into(),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// print the project's levels, one per line
+pub fn list_levels(project: &Path) -> Result<(), String> {
+    let path = FileAssetReader::get_base_path()
+        .join("assets")
+        .join(project);
+    let bytes = fs::read(&path).map_err(|err| format!("could not read {path:?}: {err}"))?;
+    let ldtk_json: LdtkJson =
+        serde_json::from_slice(&bytes).map_err(|err| format!("could not parse {path:?}: {err}"))?;
+
+    let levels = ldtk_json
+        .worlds
+        .iter()
+        .flat_map(|world| &world.levels)
+        .chain(&ldtk_json.levels);
+    for level in levels {
+        println!(
+            "{:<24} {}  {}x{} at ({}, {})",
+            level.identifier, level.iid, level.px_wid, level.px_hei, level.world_x, level.world_y
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, ArgsError> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_uses_the_defaults() {
+        assert_eq!(parse(&[]), Ok(Args::default()));
+    }
+
+    #[test]
+    fn levels_can_be_picked_by_iid_identifier_or_index() {
+        let iid = "17f6d120-c210-11ef-91ce-8301f871864b";
+        assert_eq!(parse_level(iid), LevelSelection::iid(iid));
+        assert_eq!(
+            parse_level("Level_0"),
+            LevelSelection::Identifier("Level_0".to_string())
+        );
+        assert_eq!(parse_level("3"), LevelSelection::index(3));
+        // almost an iid
+        let not_iid = "17f6d120-c210-11ef-91ce-8301f871864g";
+        assert_eq!(
+            parse_level(not_iid),
+            LevelSelection::Identifier(not_iid.to_string())
+        );
+    }
+
+    #[test]
+    fn every_option_is_parsed() {
+        let args = parse(&[
+            "levels/test/level.ldtk",
+            "--level",
+            "Level_1",
+            "--spawn",
+            "32, -16",
+            "--window",
+            "1280x720",
+            "--replay",
+            "run.json",
+            "--seed",
+            "42",
+            "--headless",
+            "--list-levels",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            Args {
+                project: PathBuf::from("levels/test/level.ldtk"),
+                player_start: PlayerStart {
+                    level: Some(LevelSelection::Identifier("Level_1".to_string())),
+                    position: Some(IVec2::new(32, -16)),
+                },
+                window_size: Some(UVec2::new(1280, 720)),
+                replay_mode: ReplayMode::Replay(PathBuf::from("run.json")),
+                seed: Some(42),
+                headless: true,
+                list_levels: true,
+                help: false,
+            }
+        );
+        assert_eq!(
+            parse(&["--record", "run.json"]).unwrap().replay_mode,
+            ReplayMode::Record(PathBuf::from("run.json"))
+        );
+        assert!(parse(&["-h"]).unwrap().help);
+    }
+
+    #[test]
+    fn bad_input_is_an_error() {
+        let invalid = |flag: &str, value: &str| ArgsError::InvalidValue {
+            flag: flag.to_string(),
+            value: value.to_string(),
+        };
+        assert_eq!(
+            parse(&["--level"]),
+            Err(ArgsError::MissingValue("--level".to_string()))
+        );
+        assert_eq!(parse(&["--spawn", "32"]), Err(invalid("--spawn", "32")));
+        assert_eq!(
+            parse(&["--window", "1280x-720"]),
+            Err(invalid("--window", "1280x-720"))
+        );
+        assert_eq!(parse(&["--seed", "abc"]), Err(invalid("--seed", "abc")));
+        assert_eq!(
+            parse(&["--fullscreen"]),
+            Err(ArgsError::UnknownFlag("--fullscreen".to_string()))
+        );
+    }
+}
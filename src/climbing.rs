@@ -2,7 +2,11 @@ use bevy::{prelude::*, utils::HashSet};
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::colliders::SensorBundle;
+use crate::{
+    colliders::SensorBundle,
+    fixed_step::GameplaySet,
+    registry::{IntCellKind, RegistryAppExt},
+};
 
 #[derive(Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Climber {
@@ -16,8 +20,11 @@ pub struct Climbable;
 #[derive(Clone, Default, Bundle, LdtkIntCell)]
 pub struct LadderBundle {
     pub climbable: Climbable,
-    #[from_int_grid_cell]
+    #[with(SensorBundle::ladder)]
     pub sensor_bundle: SensorBundle,
+    // the registry looks colliders up by value
+    #[from_int_grid_cell]
+    pub int_grid_cell: IntGridCell,
 }
 
 pub fn detect_climb_range(
@@ -72,8 +79,11 @@ pub struct ClimbingPlugin;
 
 impl Plugin for ClimbingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, detect_climb_range)
-            .add_systems(Update, ignore_gravity_if_climbing)
-            .register_ldtk_int_cell::<LadderBundle>(4);
+        app.add_systems(FixedUpdate, detect_climb_range.in_set(GameplaySet::Sense))
+            .add_systems(
+                FixedUpdate,
+                ignore_gravity_if_climbing.in_set(GameplaySet::Act),
+            )
+            .register_int_cell_kind::<LadderBundle>(IntCellKind::Ladder);
     }
 }
@@ -1,7 +1,9 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
 
+use crate::{surface::Surface, walls::OneWay};
+
 #[derive(Clone, Default, Bundle, LdtkIntCell)]
 pub struct ColliderBundle {
     pub collider: Collider,
@@ -13,57 +15,43 @@ pub struct ColliderBundle {
     pub density: ColliderMassProperties,
 }
 
-impl From<&EntityInstance> for ColliderBundle {
-    fn from(entity_instance: &EntityInstance) -> ColliderBundle {
-        let rotation_constraints = LockedAxes::ROTATION_LOCKED;
+// Constructors for `#[with(...)]` on each kind's bundle. The registry can
+// swap the collider for another shape once they've spawned
 
-        match entity_instance.identifier.as_ref() {
-            "Player" => ColliderBundle {
-                collider: Collider::compound(vec![
-                    (Vect::new(0., 2.), 0., Collider::cuboid(6., 2.)),
-                    (Vect::new(0., -4.), 0., Collider::cuboid(6., 4.)),
-                ]),
-                rigid_body: RigidBody::Dynamic,
-                friction: Friction {
-                    coefficient: 1.0,
-                    combine_rule: CoefficientCombineRule::Min,
-                },
-                rotation_constraints,
-                ..Default::default()
-            },
-            "Obstacle" => ColliderBundle {
-                collider: Collider::cuboid(6., 6.),
-                rigid_body: RigidBody::KinematicVelocityBased,
-                rotation_constraints,
-                ..Default::default()
-            },
-            "Mob" => ColliderBundle {
-                collider: Collider::cuboid(6., 6.),
-                rigid_body: RigidBody::KinematicVelocityBased,
-                rotation_constraints,
-                ..Default::default()
-            },
-            "Platform" => ColliderBundle {
-                collider: Collider::cuboid(8., 8.),
-                rigid_body: RigidBody::KinematicVelocityBased,
-                friction: Friction::new(1.0),
-                rotation_constraints,
-                ..Default::default()
+impl ColliderBundle {
+    pub fn player(_: &EntityInstance) -> ColliderBundle {
+        ColliderBundle {
+            collider: Collider::compound(vec![
+                (Vect::new(0., 2.), 0., Collider::cuboid(6., 2.)),
+                (Vect::new(0., -4.), 0., Collider::cuboid(6., 4.)),
+            ]),
+            rigid_body: RigidBody::Dynamic,
+            friction: Friction {
+                coefficient: 1.0,
+                combine_rule: CoefficientCombineRule::Min,
             },
-            "Player_Respawn" => ColliderBundle {
-                collider: Collider::cuboid(8., 8.),
-                rigid_body: RigidBody::KinematicVelocityBased,
-                friction: Friction::new(1.0),
-                rotation_constraints,
-                ..Default::default()
-            },
-            entity_id => {
-                warn!(
-                    "Unexpected entity when creating ColliderBundle {}",
-                    entity_id
-                );
-                ColliderBundle::default()
-            }
+            rotation_constraints: LockedAxes::ROTATION_LOCKED,
+            ..Default::default()
+        }
+    }
+
+    /// mobs & obstacles
+    pub fn walker(_: &EntityInstance) -> ColliderBundle {
+        ColliderBundle {
+            collider: Collider::cuboid(6., 6.),
+            rigid_body: RigidBody::KinematicVelocityBased,
+            rotation_constraints: LockedAxes::ROTATION_LOCKED,
+            ..Default::default()
+        }
+    }
+
+    pub fn platform(_: &EntityInstance) -> ColliderBundle {
+        ColliderBundle {
+            collider: Collider::cuboid(8., 8.),
+            rigid_body: RigidBody::KinematicVelocityBased,
+            friction: Friction::new(1.0),
+            rotation_constraints: LockedAxes::ROTATION_LOCKED,
+            ..Default::default()
         }
     }
 }
@@ -76,29 +64,64 @@ pub struct SensorBundle {
     pub rotation_constraints: LockedAxes,
 }
 
-impl From<IntGridCell> for SensorBundle {
-    fn from(int_grid_cell: IntGridCell) -> SensorBundle {
-        let rotation_constraints = LockedAxes::ROTATION_LOCKED;
-        match int_grid_cell.value {
-            // ladder
-            4 => SensorBundle {
-                collider: Collider::cuboid(8., 8.),
-                sensor: Sensor,
-                rotation_constraints,
-                active_events: ActiveEvents::COLLISION_EVENTS,
-            },
-            // spike
-            5 => SensorBundle {
-                collider: Collider::compound(vec![(
-                    Vect::new(0., -3.),
-                    0.,
-                    Collider::cuboid(8., 5.),
-                )]),
-                sensor: Sensor,
-                rotation_constraints,
-                active_events: ActiveEvents::COLLISION_EVENTS,
-            },
-            _ => SensorBundle::default(),
+impl SensorBundle {
+    pub fn ladder(_: IntGridCell) -> SensorBundle {
+        SensorBundle {
+            collider: Collider::cuboid(8., 8.),
+            sensor: Sensor,
+            rotation_constraints: LockedAxes::ROTATION_LOCKED,
+            active_events: ActiveEvents::COLLISION_EVENTS,
+        }
+    }
+
+    pub fn spike(_: IntGridCell) -> SensorBundle {
+        SensorBundle {
+            collider: Collider::compound(vec![(Vect::new(0., -3.), 0., Collider::cuboid(8., 5.))]),
+            sensor: Sensor,
+            rotation_constraints: LockedAxes::ROTATION_LOCKED,
+            active_events: ActiveEvents::COLLISION_EVENTS,
+        }
+    }
+}
+
+/// Physics hooks for colliders that don't just block everything, like one-way
+/// platforms which only block things landing on them from above, & conveyors
+/// which carry things along
+#[derive(SystemParam)]
+pub struct ContactHooks<'w, 's> {
+    one_way: Query<'w, 's, (), With<OneWay>>,
+    surfaces: Query<'w, 's, &'static Surface>,
+}
+
+impl BevyPhysicsHooks for ContactHooks<'_, '_> {
+    fn modify_solver_contacts(&self, context: ContactModificationContextView) {
+        // the surface moves relative to whatever's touching it, from collider
+        // 1's point of view
+        let surface_velocity = match (
+            self.surfaces.get(context.collider1()),
+            self.surfaces.get(context.collider2()),
+        ) {
+            (Ok(surface), _) => surface.base_velocity(),
+            (_, Ok(surface)) => -surface.base_velocity(),
+            _ => Vect::ZERO,
+        };
+        if surface_velocity != Vect::ZERO {
+            for solver_contact in context.raw.solver_contacts.iter_mut() {
+                solver_contact.tangent_velocity = surface_velocity.into();
+            }
         }
+
+        // the direction things can be pushed out of the platform, from
+        // collider 1's point of view
+        let allowed_normal = if self.one_way.contains(context.collider1()) {
+            Vect::Y
+        } else if self.one_way.contains(context.collider2()) {
+            -Vect::Y
+        } else {
+            return;
+        };
+        context
+            .raw
+            .update_as_oneway_platform(&allowed_normal.into(), 0.1);
     }
 }
@@ -0,0 +1,370 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::{actions::PlatformerAction, player::Player, save};
+
+// controls constants
+
+/// where the user's bindings are saved, inside the config directory
+const BINDINGS_FILE: &str = "bean-platformer/bindings.json";
+
+/// how far the left stick has to be pushed before it counts as pressed
+const STICK_DEADZONE: f32 = 0.3;
+
+/// opens & closes the controls menu
+const MENU_KEY: KeyCode = KeyCode::F1;
+const MENU_BUTTON: GamepadButton = GamepadButton::Start;
+
+/// The player's bindings. Loaded at startup from the user's bindings file, if
+/// they have one, & saved whenever they're changed in the controls menu.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct Bindings(pub InputMap<PlatformerAction>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut input_map = InputMap::default()
+            // keyboard
+            .with(PlatformerAction::Jump, KeyCode::Space)
+            .with(PlatformerAction::Right, KeyCode::ArrowRight)
+            .with(PlatformerAction::Left, KeyCode::ArrowLeft)
+            .with(PlatformerAction::Up, KeyCode::ArrowUp)
+            .with(PlatformerAction::Down, KeyCode::ArrowDown)
+            // gamepad
+            .with(PlatformerAction::Jump, GamepadButton::South)
+            .with(PlatformerAction::Right, GamepadButton::DPadRight)
+            .with(PlatformerAction::Left, GamepadButton::DPadLeft)
+            .with(PlatformerAction::Up, GamepadButton::DPadUp)
+            .with(PlatformerAction::Down, GamepadButton::DPadDown)
+            .with(
+                PlatformerAction::Right,
+                GamepadControlDirection::LEFT_RIGHT.threshold(STICK_DEADZONE),
+            )
+            .with(
+                PlatformerAction::Left,
+                GamepadControlDirection::LEFT_LEFT.threshold(STICK_DEADZONE),
+            )
+            .with(
+                PlatformerAction::Up,
+                GamepadControlDirection::LEFT_UP.threshold(STICK_DEADZONE),
+            )
+            .with(
+                PlatformerAction::Down,
+                GamepadControlDirection::LEFT_DOWN.threshold(STICK_DEADZONE),
+            );
+        // respawning at will is for testing levels, players respawn by dying
+        #[cfg(feature = "dev")]
+        input_map
+            .insert(PlatformerAction::RespawnLevel, KeyCode::KeyR)
+            .insert(PlatformerAction::RespawnWorld, KeyCode::KeyG)
+            .insert(PlatformerAction::RespawnLevel, GamepadButton::Select);
+        Self(input_map)
+    }
+}
+
+impl Bindings {
+    /// the user's bindings file, in the platform's config directory
+    fn path() -> Option<PathBuf> {
+        save::config_path(BINDINGS_FILE)
+    }
+
+    /// load the user's bindings, falling back to the defaults if they don't
+    /// have any or they can't be read
+    fn load() -> Self {
+        let Some(path) = Self::path().filter(|path| path.exists()) else {
+            return Self::default();
+        };
+        match fs::read(&path)
+            .map(|bytes| serde_json::from_slice::<InputMap<PlatformerAction>>(&bytes))
+        {
+            #[allow(unused_mut)]
+            Ok(Ok(mut input_map)) => {
+                // bindings saved by a dev build can have the debug actions
+                #[cfg(not(feature = "dev"))]
+                for action in PlatformerAction::DEV {
+                    input_map.clear_action(&action);
+                }
+                Self(input_map)
+            }
+            Ok(Err(err)) => {
+                warn!("could not parse bindings {path:?}, using the defaults: {err}");
+                Self::default()
+            }
+            Err(err) => {
+                warn!("could not read bindings {path:?}, using the defaults: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("could not find a config directory to save bindings to");
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json =
+                    serde_json::to_string_pretty(&self.0).expect("bindings should serialize");
+                fs::write(&path, json)
+            });
+        if let Err(err) = result {
+            warn!("could not save bindings to {path:?}: {err}");
+        }
+    }
+
+    /// replace the bindings of the same kind as `input`, e.g. binding a key
+    /// replaces the action's keys but leaves its gamepad bindings alone
+    fn rebind<T: Buttonlike>(&mut self, action: PlatformerAction, input: T) {
+        if let Some(inputs) = self.0.get_buttonlike_mut(&action) {
+            inputs.retain(|bound| (**bound).as_reflect().downcast_ref::<T>().is_none());
+        }
+        self.0.insert(action, input);
+    }
+
+    /// names of the action's bindings of type T, for showing in the menu
+    fn names<T: Buttonlike>(&self, action: PlatformerAction) -> String {
+        let names: Vec<String> = self
+            .0
+            .get_buttonlike(&action)
+            .into_iter()
+            .flatten()
+            .filter_map(|bound| (**bound).as_reflect().downcast_ref::<T>())
+            .map(|bound| format!("{bound:?}"))
+            .collect();
+        if names.is_empty() {
+            "-".to_string()
+        } else {
+            names.join(", ")
+        }
+    }
+}
+
+fn load_bindings(mut commands: Commands) {
+    commands.insert_resource(Bindings::load());
+}
+
+/// keep the player's input map up to date with the bindings
+fn apply_bindings(
+    mut commands: Commands,
+    bindings: Res<Bindings>,
+    added_players: Query<Entity, Added<Player>>,
+    mut players: Query<&mut InputMap<PlatformerAction>, With<Player>>,
+) {
+    for player_ent in &added_players {
+        commands
+            .entity(player_ent)
+            .insert(InputManagerBundle::with_map(bindings.0.clone()));
+    }
+    if bindings.is_changed() {
+        for mut input_map in &mut players {
+            *input_map = bindings.0.clone();
+        }
+    }
+}
+
+// CONTROLS MENU
+
+/// state of the in-game controls menu, where the player can rebind actions
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+struct ControlsMenu {
+    open: bool,
+    /// index into [`PlatformerAction::ALL`]
+    selected: usize,
+    /// waiting for the player to press the new key or button for the selected
+    /// action
+    listening: bool,
+}
+
+#[derive(Component)]
+struct ControlsMenuRoot;
+
+/// the first key or gamepad button pressed this frame
+fn just_pressed_input(
+    keys: &ButtonInput<KeyCode>,
+    gamepads: &Query<&Gamepad>,
+) -> (Option<KeyCode>, Option<GamepadButton>) {
+    (
+        keys.get_just_pressed().next().copied(),
+        gamepads
+            .iter()
+            .find_map(|gamepad| gamepad.get_just_pressed().next().copied()),
+    )
+}
+
+/// open & close the menu, pausing the player's controls while it's open
+fn toggle_controls_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut menu: ResMut<ControlsMenu>,
+    mut players: Query<&mut ActionState<PlatformerAction>, With<Player>>,
+) {
+    if menu.listening {
+        return;
+    }
+    let (key, button) = just_pressed_input(&keys, &gamepads);
+    let toggle = key == Some(MENU_KEY) || button == Some(MENU_BUTTON);
+    let close = menu.open && (key == Some(KeyCode::Escape) || button == Some(GamepadButton::East));
+    if !toggle && !close {
+        return;
+    }
+
+    menu.open = !menu.open;
+    menu.selected = 0;
+    for mut action_state in &mut players {
+        if menu.open {
+            action_state.disable();
+        } else {
+            action_state.enable();
+        }
+    }
+}
+
+/// move through the menu, start rebinding or reset to the defaults
+fn navigate_controls_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut menu: ResMut<ControlsMenu>,
+    mut bindings: ResMut<Bindings>,
+) {
+    if !menu.open || menu.listening {
+        return;
+    }
+    let action_count = PlatformerAction::ALL.len();
+    match just_pressed_input(&keys, &gamepads) {
+        (Some(KeyCode::ArrowUp), _) | (_, Some(GamepadButton::DPadUp)) => {
+            menu.selected = (menu.selected + action_count - 1) % action_count;
+        }
+        (Some(KeyCode::ArrowDown), _) | (_, Some(GamepadButton::DPadDown)) => {
+            menu.selected = (menu.selected + 1) % action_count;
+        }
+        (Some(KeyCode::Enter), _) | (_, Some(GamepadButton::South)) => {
+            menu.listening = true;
+        }
+        (Some(KeyCode::Backspace), _) | (_, Some(GamepadButton::West)) => {
+            *bindings = Bindings::default();
+            bindings.save();
+        }
+        _ => (),
+    }
+}
+
+/// bind the next key or gamepad button pressed to the selected action
+fn listen_for_binding(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut menu: ResMut<ControlsMenu>,
+    mut bindings: ResMut<Bindings>,
+) {
+    if !menu.listening {
+        return;
+    }
+    let action = PlatformerAction::ALL[menu.selected];
+    match just_pressed_input(&keys, &gamepads) {
+        // don't let the menu controls get bound, so the menu can't be locked
+        (Some(KeyCode::Escape | MENU_KEY), _) | (_, Some(MENU_BUTTON)) => {
+            menu.listening = false;
+        }
+        (Some(key), _) => {
+            bindings.rebind(action, key);
+            bindings.save();
+            menu.listening = false;
+        }
+        (_, Some(button)) => {
+            bindings.rebind(action, button);
+            bindings.save();
+            menu.listening = false;
+        }
+        _ => (),
+    }
+}
+
+/// rebuild the menu whenever it or the bindings change
+fn draw_controls_menu(
+    mut commands: Commands,
+    menu: Res<ControlsMenu>,
+    bindings: Res<Bindings>,
+    roots: Query<Entity, With<ControlsMenuRoot>>,
+) {
+    if !menu.is_changed() && !bindings.is_changed() {
+        return;
+    }
+    for root in &roots {
+        commands.entity(root).despawn_recursive();
+    }
+    if !menu.open {
+        return;
+    }
+
+    commands
+        .spawn((
+            ControlsMenuRoot,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.),
+                ..default()
+            },
+            BackgroundColor(Color::BLACK.with_alpha(0.8)),
+            GlobalZIndex(i32::MAX),
+        ))
+        .with_children(|menu_builder| {
+            menu_builder.spawn(Text::new("Controls"));
+            for (i, &action) in PlatformerAction::ALL.iter().enumerate() {
+                let selected = i == menu.selected;
+                let keys = if selected && menu.listening {
+                    "press a key or button...".to_string()
+                } else {
+                    format!(
+                        "{}  |  {}",
+                        bindings.names::<KeyCode>(action),
+                        bindings.names::<GamepadButton>(action)
+                    )
+                };
+                menu_builder.spawn((
+                    Text::new(format!("{action:?}: {keys}")),
+                    TextFont::from_font_size(14.),
+                    TextColor(if selected {
+                        Color::srgb(1., 0.85, 0.3)
+                    } else {
+                        Color::WHITE
+                    }),
+                ));
+            }
+            menu_builder.spawn((
+                Text::new("up/down: select   enter: rebind   backspace: reset   esc: close"),
+                TextFont::from_font_size(10.),
+            ));
+        });
+}
+
+/// gamepad & keyboard controls, rebindable from an in-game menu opened with F1
+/// or start
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ControlsMenu>()
+            .add_systems(Startup, load_bindings)
+            .add_systems(
+                Update,
+                (
+                    apply_bindings,
+                    (
+                        toggle_controls_menu,
+                        listen_for_binding,
+                        navigate_controls_menu,
+                        draw_controls_menu,
+                    )
+                        .chain(),
+                ),
+            );
+    }
+}
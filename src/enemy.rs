@@ -2,20 +2,32 @@ use bevy::prelude::*;
 use bevy_ecs_ldtk::{prelude::*, utils::ldtk_pixel_coords_to_translation_pivoted};
 use bevy_rapier2d::dynamics::Velocity;
 
-use crate::colliders::ColliderBundle;
+use crate::{
+    animation_controller::AnimationController,
+    colliders::ColliderBundle,
+    fixed_step::GameplaySet,
+    level_data::{Checked, FromEntityChecked, LevelDataError},
+    player::Player,
+    registry::{EntityKind, RegistryAppExt},
+};
+
+/// how close the player has to be for a mob to attack
+const MOB_ATTACK_DISTANCE: f32 = 20.;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Enemy;
 
-#[derive(Clone, Default, Bundle, LdtkEntity)]
+#[derive(Default, Bundle, LdtkEntity)]
 pub struct MobBundle {
     #[sprite_sheet]
     pub sprite_sheet: Sprite,
-    #[from_entity_instance]
+    #[with(ColliderBundle::walker)]
     pub collider_bundle: ColliderBundle,
+    #[with(AnimationController::mob)]
+    pub animation_controller: AnimationController,
     pub enemy: Enemy,
     #[ldtk_entity]
-    pub patrol: Patrol,
+    pub patrol: Checked<Patrol>,
 }
 
 #[derive(Clone, PartialEq, Debug, Default, Component)]
@@ -25,15 +37,11 @@ pub struct Patrol {
     pub forward: bool,
 }
 
-impl LdtkEntity for Patrol {
-    fn bundle_entity(
+impl FromEntityChecked for Patrol {
+    fn from_entity_checked(
         entity_instance: &EntityInstance,
         layer_instance: &LayerInstance,
-        _: Option<&Handle<Image>>,
-        _: Option<&TilesetDefinition>,
-        _: &AssetServer,
-        _: &mut Assets<TextureAtlasLayout>,
-    ) -> Patrol {
+    ) -> (Patrol, Option<LevelDataError>) {
         let mut points = Vec::new();
         points.push(ldtk_pixel_coords_to_translation_pivoted(
             entity_instance.px,
@@ -42,9 +50,21 @@ impl LdtkEntity for Patrol {
             entity_instance.pivot,
         ));
 
-        let ldtk_patrol_points = entity_instance
-            .iter_points_field("patrol")
-            .expect("patrol field should be correclty typed, expected Array<Point>");
+        // without a patrol, stay where the entity was placed
+        let ldtk_patrol_points = match entity_instance.iter_points_field("patrol") {
+            Ok(ldtk_patrol_points) => ldtk_patrol_points,
+            Err(err) => {
+                let patrol = Patrol {
+                    points,
+                    index: 0,
+                    forward: true,
+                };
+                return (
+                    patrol,
+                    Some(LevelDataError::entity_field(entity_instance, err)),
+                );
+            }
+        };
 
         for ldtk_point in ldtk_patrol_points {
             // The +1 is necessary here due to the pivot of the entities in the sample
@@ -63,11 +83,12 @@ impl LdtkEntity for Patrol {
             ));
         }
 
-        Patrol {
+        let patrol = Patrol {
             points,
             index: 1,
             forward: true,
-        }
+        };
+        (patrol, None)
     }
 }
 
@@ -104,11 +125,32 @@ pub fn patrol(mut query: Query<(&mut Transform, &mut Velocity, &mut Patrol), Wit
     }
 }
 
+/// mobs attack while the player is in reach
+pub fn attack_player_in_range(
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut mob_query: Query<(&GlobalTransform, &mut AnimationController), With<Enemy>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    for (mob_transform, mut controller) in &mut mob_query {
+        let in_range = mob_transform
+            .translation()
+            .truncate()
+            .distance(player_transform.translation().truncate())
+            < MOB_ATTACK_DISTANCE;
+        if controller.has_flag("attack") != in_range {
+            controller.set_flag("attack", in_range);
+        }
+    }
+}
+
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, patrol)
-            .register_ldtk_entity::<MobBundle>("Mob");
+        app.add_systems(FixedUpdate, patrol.in_set(GameplaySet::Act))
+            .add_systems(Update, attack_player_in_range)
+            .register_entity_kind::<MobBundle>(EntityKind::Mob);
     }
 }
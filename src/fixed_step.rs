@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+// fixed step constants
+
+/// if something moves further than this in a single tick, it was teleported
+/// rather than moved, so we don't interpolate it
+const TELEPORT_DISTANCE: f32 = 32.;
+
+/// Gameplay that runs in `FixedUpdate`, before the physics step. Systems that
+/// read collisions go in `Sense`, & systems that use them to move things go in
+/// `Act`, so the order is the same every tick.
+#[derive(SystemSet, Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum GameplaySet {
+    Sense,
+    Act,
+}
+
+/// Smooths out things moved by physics, which only move on fixed ticks.
+/// Outside of the fixed loop the transform is drawn between the last two
+/// ticks, & inside it the transform is put back to where physics left it.
+#[derive(Component, Clone, Copy, PartialEq, Debug, Default)]
+pub struct TransformInterpolation {
+    previous: Option<Vec2>,
+    /// where physics left it on the last fixed tick
+    pub(crate) current: Option<Vec2>,
+    // what we last set the transform to, to tell if something else moved it
+    rendered: Option<Vec2>,
+}
+
+/// interpolate everything that physics moves
+#[allow(clippy::type_complexity)]
+fn add_transform_interpolation(
+    mut commands: Commands,
+    query: Query<(Entity, &RigidBody), (Added<RigidBody>, Without<TransformInterpolation>)>,
+) {
+    for (ent, rigid_body) in &query {
+        if *rigid_body != RigidBody::Fixed {
+            commands
+                .entity(ent)
+                .insert(TransformInterpolation::default());
+        }
+    }
+}
+
+/// keep rapier's timestep the same as the fixed timestep
+fn sync_physics_timestep(time: Res<Time>, mut timestep_mode: ResMut<TimestepMode>) {
+    if let TimestepMode::Fixed { dt, .. } = timestep_mode.as_mut() {
+        if *dt != time.delta_secs() {
+            *dt = time.delta_secs();
+        }
+    }
+}
+
+/// before the fixed ticks, put things back where physics left them. If
+/// something outside of the fixed loop moved them, e.g. respawning, keep that
+fn restore_simulated_transforms(mut query: Query<(&mut TransformInterpolation, &mut Transform)>) {
+    for (mut interpolation, mut transform) in &mut query {
+        let translation = transform.translation.truncate();
+        if interpolation.rendered != Some(translation) {
+            interpolation.previous = Some(translation);
+            interpolation.current = Some(translation);
+        } else if let Some(current) = interpolation.current {
+            transform.translation = current.extend(transform.translation.z);
+        }
+    }
+}
+
+/// after each fixed tick, remember where physics moved things to
+fn record_simulated_transforms(mut query: Query<(&mut TransformInterpolation, &Transform)>) {
+    for (mut interpolation, transform) in &mut query {
+        let translation = transform.translation.truncate();
+        let previous = interpolation.current.unwrap_or(translation);
+        interpolation.previous = if previous.distance(translation) > TELEPORT_DISTANCE {
+            Some(translation)
+        } else {
+            Some(previous)
+        };
+        interpolation.current = Some(translation);
+    }
+}
+
+/// after the fixed ticks, draw things between the last two ticks
+fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&mut TransformInterpolation, &mut Transform)>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (mut interpolation, mut transform) in &mut query {
+        let (Some(previous), Some(current)) = (interpolation.previous, interpolation.current)
+        else {
+            continue;
+        };
+        let translation = previous.lerp(current, alpha);
+        transform.translation = translation.extend(transform.translation.z);
+        interpolation.rendered = Some(translation);
+    }
+}
+
+/// steps gameplay & physics on a fixed timestep, & interpolates what they move
+/// so it still draws smoothly at any frame rate
+pub struct FixedStepPlugin;
+
+impl Plugin for FixedStepPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TimestepMode::Fixed {
+            dt: Time::<Fixed>::default().timestep().as_secs_f32(),
+            substeps: 1,
+        })
+        .configure_sets(
+            FixedUpdate,
+            (GameplaySet::Sense, GameplaySet::Act)
+                .chain()
+                .before(PhysicsSet::SyncBackend),
+        )
+        .add_systems(Update, add_transform_interpolation)
+        .add_systems(FixedFirst, sync_physics_timestep)
+        .add_systems(FixedLast, record_simulated_transforms)
+        .add_systems(
+            RunFixedMainLoop,
+            (
+                restore_simulated_transforms.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+                interpolate_transforms.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+            ),
+        );
+    }
+}
@@ -1,10 +1,17 @@
 use std::path::PathBuf;
 
 use bevy::{asset::AssetPath, prelude::*};
-use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::{ldtk::Level, prelude::*, utils::ldtk_pixel_coords_to_translation_pivoted};
 use bevy_rapier2d::prelude::*;
 
-use crate::player::Player;
+use crate::{
+    camera::CameraController,
+    ground_pound::GroundPounder,
+    level_data::{self, LevelDataErrors},
+    player::Player,
+    registry::{EntityKind, Registry, RegistryAppExt},
+    room_transition::RoomTransitionEvent,
+};
 
 #[derive(Resource)]
 pub struct GameFile {
@@ -17,7 +24,7 @@ pub fn setup(
     asset_server: Res<AssetServer>,
     mut rapier_config: Query<&mut RapierConfiguration>,
 ) {
-    commands.spawn(Camera2d);
+    commands.spawn((Camera2d, CameraController::default()));
 
     rapier_config.single_mut().gravity = Vec2::new(0.0, -2000.0);
 
@@ -30,21 +37,44 @@ pub fn setup(
     });
 }
 
+/// Name of the optional bool level field that disables the kill plane. Set it
+/// on levels whose bottom edge opens into a neighbouring level, so falling off
+/// the bottom moves the player into the next level instead of respawning them.
+pub(crate) const CONNECTS_DOWN_FIELD: &str = "connects_down";
+
+/// How far below the bottom edge of the level the player can fall before
+/// they're considered out of bounds
+const KILL_PLANE_MARGIN: f32 = 32.;
+
+#[allow(clippy::too_many_arguments)]
 pub fn update_level_selection(
     level_query: Query<(&LevelIid, &Transform), Without<Player>>,
     player_query: Query<&Transform, With<Player>>,
     mut level_selection: ResMut<LevelSelection>,
     ldtk_projects: Query<&LdtkProjectHandle>,
     ldtk_project_assets: Res<Assets<LdtkProject>>,
+    mut level_respawn_event: EventWriter<RespawnLevelEvent>,
+    mut room_transition_event: EventWriter<RoomTransitionEvent>,
+    mut level_data_errors: ResMut<LevelDataErrors>,
 ) {
+    let Some(ldtk_project) = level_data::loaded_project(&ldtk_projects, &ldtk_project_assets)
+    else {
+        return;
+    };
+
+    // Track if the player is inside any level, and the kill plane of the
+    // currently selected level (if it has one)
+    let mut player_in_any_level = false;
+    let mut opt_kill_plane_y = None;
+
     for (level_iid, level_transform) in &level_query {
-        // Load the project & level
-        let ldtk_project = ldtk_project_assets
-            .get(ldtk_projects.single())
-            .expect("Project should be loaded if level has spawned");
-        let level = ldtk_project
-            .get_raw_level_by_iid(&level_iid.to_string())
-            .expect("Spawned level should exist in LDtk project");
+        let level = match level_data::raw_level(ldtk_project, level_iid) {
+            Ok(level) => level,
+            Err(err) => {
+                level_data::report(&mut level_data_errors, err);
+                continue;
+            }
+        };
 
         // Get the bounds of the level
         let level_bounds = Rect {
@@ -56,25 +86,46 @@ pub fn update_level_selection(
         };
 
         // Check if the level is an iid
-        let is_iid = match *level_selection {
-            LevelSelection::Iid(_) => true,
-            _ => false,
-        };
+        let is_iid = matches!(*level_selection, LevelSelection::Iid(_));
         // Ensure that the level_selection is always an iid. This is needed by
         // the respawn logic
         if level_selection.is_match(&LevelIndices::default(), level) && !is_iid {
             *level_selection = LevelSelection::iid(level.iid.clone());
         }
 
+        // If this is the selected level, record its kill plane. Levels that
+        // connect downward to a neighbour opt out of the kill plane.
+        if level_selection.is_match(&LevelIndices::default(), level) {
+            let connects_down = level
+                .get_bool_field(CONNECTS_DOWN_FIELD)
+                .copied()
+                .unwrap_or(false);
+            if !connects_down {
+                opt_kill_plane_y = Some(level_bounds.min.y - KILL_PLANE_MARGIN);
+            }
+        }
+
         // Check if the player is in bounds of another level
         for player_transform in &player_query {
-            if player_transform.translation.x < level_bounds.max.x
+            let player_in_level = player_transform.translation.x < level_bounds.max.x
                 && player_transform.translation.x > level_bounds.min.x
                 && player_transform.translation.y < level_bounds.max.y
-                && player_transform.translation.y > level_bounds.min.y
-                && !level_selection.is_match(&LevelIndices::default(), level)
-            {
+                && player_transform.translation.y > level_bounds.min.y;
+            player_in_any_level |= player_in_level;
+
+            if player_in_level && !level_selection.is_match(&LevelIndices::default(), level) {
                 *level_selection = LevelSelection::iid(level.iid.clone());
+                room_transition_event.send(RoomTransitionEvent { level_bounds });
+            }
+        }
+    }
+
+    // If the player fell out of the bottom of the selected level and didn't
+    // land in another level, respawn the level
+    if let Some(kill_plane_y) = opt_kill_plane_y {
+        for player_transform in &player_query {
+            if !player_in_any_level && player_transform.translation.y < kill_plane_y {
+                level_respawn_event.send(RespawnLevelEvent::RespawnLevelEvent);
             }
         }
     }
@@ -96,7 +147,9 @@ fn respawn_world(
     if !respawn_events.is_empty() {
         respawn_events.clear();
 
-        commands.entity(ldtk_projects.single()).despawn_recursive();
+        for ldtk_project in &ldtk_projects {
+            commands.entity(ldtk_project).despawn_recursive();
+        }
 
         let ldtk_handle = asset_server
             .load(AssetPath::from_path(&game_file.path))
@@ -115,6 +168,7 @@ pub enum RespawnLevelEvent {
 
 /// Respawn the current level and move the player to that level's respawn point.
 /// If the level has not respawn point, do nothing
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn respawn_level(
     mut commands: Commands,
     ldtk_projects: Query<&LdtkProjectHandle>,
@@ -124,7 +178,11 @@ fn respawn_level(
     level_selection: Res<LevelSelection>,
     levels: Query<(Entity, &LevelIid)>,
     player_respawns: Query<(&PlayerRespawn, &Transform), Without<Player>>,
-    mut players: Query<&mut Transform, (With<Player>, Without<PlayerRespawn>)>,
+    mut players: Query<
+        (&mut Transform, &mut Velocity, &mut GroundPounder),
+        (With<Player>, Without<PlayerRespawn>),
+    >,
+    mut level_data_errors: ResMut<LevelDataErrors>,
 ) {
     if !respawn_events.is_empty() {
         respawn_events.clear();
@@ -134,12 +192,18 @@ fn respawn_level(
             for (level_ent, level_iid) in levels.iter() {
                 if level_iid == level_selection_iid {
                     // Then, get some level metadata
-                    let ldtk_project = ldtk_project_assets
-                        .get(ldtk_projects.single())
-                        .expect("Project should be loaded during respawning");
-                    let level = ldtk_project
-                        .get_raw_level_by_iid(level_iid.get())
-                        .expect("Level should be loaded during respawning");
+                    let Some(ldtk_project) =
+                        level_data::loaded_project(&ldtk_projects, &ldtk_project_assets)
+                    else {
+                        return;
+                    };
+                    let level = match level_data::raw_level(ldtk_project, level_iid) {
+                        Ok(level) => level,
+                        Err(err) => {
+                            level_data::report(&mut level_data_errors, err);
+                            return;
+                        }
+                    };
 
                     // Find the correct respawn point for the currently level
                     // selected level
@@ -160,9 +224,15 @@ fn respawn_level(
                     // respawn point
                     if let Some(respawn_point) = opt_respawn_point {
                         commands.entity(level_ent).insert(Respawn);
-                        for mut player_transform in players.iter_mut() {
+                        for (mut player_transform, mut velocity, mut ground_pounder) in
+                            players.iter_mut()
+                        {
                             player_transform.translation.x = respawn_point.x;
                             player_transform.translation.y = respawn_point.y;
+                            *velocity = Velocity::zero();
+                            // a pound that ended in the kill plane or on spikes
+                            // shouldn't land at the respawn point
+                            *ground_pounder = GroundPounder::NotPounding;
                         }
                     }
                 }
@@ -208,6 +278,84 @@ impl LdtkEntity for PlayerRespawnBundle {
     }
 }
 
+// Player start
+
+/// Where the player starts, instead of where they're placed in the project.
+/// Set from the command line, & applied whenever the player spawns
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+pub struct PlayerStart {
+    /// the level to start in, at its Player_Respawn unless `position` is set.
+    /// Defaults to the selected level
+    pub level: Option<LevelSelection>,
+    /// pixel position in the start level, as shown in LDtk
+    pub position: Option<IVec2>,
+}
+
+/// The world position of a level's Player_Respawn, read from the project so
+/// it works even if the level isn't spawned
+fn level_respawn_point(level: &Level, registry: &Registry) -> Option<Vec2> {
+    let respawn = level
+        .layer_instances
+        .iter()
+        .flatten()
+        .flat_map(|layer_instance| &layer_instance.entity_instances)
+        .find(|entity_instance| {
+            registry.entity_kind(&entity_instance.identifier) == Some(EntityKind::PlayerRespawn)
+        })?;
+    let in_level = ldtk_pixel_coords_to_translation_pivoted(
+        respawn.px,
+        level.px_hei,
+        IVec2::new(respawn.width, respawn.height),
+        respawn.pivot,
+    );
+    Some(Vec2::new(level.world_x as f32, -(level.world_y + level.px_hei) as f32) + in_level)
+}
+
+/// move newly spawned players to the start, & select the start level so it
+/// spawns around them
+fn move_player_to_start(
+    player_start: Res<PlayerStart>,
+    registry: Res<Registry>,
+    mut level_selection: ResMut<LevelSelection>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+    mut players: Query<&mut Transform, Added<Player>>,
+) {
+    if *player_start == PlayerStart::default() || players.is_empty() {
+        return;
+    }
+    let Some(ldtk_project) = level_data::loaded_project(&ldtk_projects, &ldtk_project_assets)
+    else {
+        return;
+    };
+    let start_level_selection = player_start.level.as_ref().unwrap_or(&level_selection);
+    let Some(level) = ldtk_project.find_raw_level_by_level_selection(start_level_selection) else {
+        error!("start level {start_level_selection:?} isn't in the project");
+        return;
+    };
+
+    let start = match player_start.position {
+        Some(position) => Some(Vec2::new(
+            (level.world_x + position.x) as f32,
+            -(level.world_y + position.y) as f32,
+        )),
+        None => level_respawn_point(level, &registry),
+    };
+    let Some(start) = start else {
+        error!(
+            "{} has no Player_Respawn, pick where to start with --spawn",
+            level.identifier
+        );
+        return;
+    };
+
+    for mut player_transform in &mut players {
+        player_transform.translation.x = start.x;
+        player_transform.translation.y = start.y;
+    }
+    *level_selection = LevelSelection::iid(level.iid.clone());
+}
+
 // Plugin Wireup
 
 pub struct GameFlowPlugin;
@@ -215,10 +363,17 @@ pub struct GameFlowPlugin;
 impl Plugin for GameFlowPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup)
-            .register_ldtk_entity::<PlayerRespawnBundle>("Player_Respawn")
+            .register_entity_kind::<PlayerRespawnBundle>(EntityKind::PlayerRespawn)
             .add_event::<RespawnWorldEvent>()
             .add_event::<RespawnLevelEvent>()
+            .init_resource::<PlayerStart>()
             .add_systems(Update, update_level_selection)
+            // players are positioned relative to their level until they're
+            // adopted by the world
+            .add_systems(
+                PostUpdate,
+                move_player_to_start.after(bevy_ecs_ldtk::systems::worldly_adoption),
+            )
             .add_systems(Update, (respawn_world, respawn_level));
     }
 }
@@ -2,7 +2,7 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use std::{collections::HashSet, time::Duration};
 
-use crate::timer_helpers::TimerHelper;
+use crate::{fixed_step::GameplaySet, timer_helpers::TimerHelper};
 
 // ground detection
 
@@ -13,7 +13,7 @@ pub struct GroundSensor {
     pub intersecting_ground_entities: HashSet<Entity>,
 }
 
-///
+/// tracks if an entity is on the ground, and if so which entity it's standing on
 #[derive(Clone, Component, Default)]
 pub enum GroundDetection {
     OnGround(Entity),
@@ -23,12 +23,22 @@ pub enum GroundDetection {
 impl GroundDetection {
     pub fn on_ground(&self) -> bool {
         match self {
-            &GroundDetection::OnGround(_) => true,
-            &GroundDetection::NotOnGround => false,
+            GroundDetection::OnGround(_) => true,
+            GroundDetection::NotOnGround => false,
         }
     }
 }
 
+/// sent when something with [`GroundDetection`] lands. strength is how fast it
+/// was falling, in pixels per second
+#[derive(Event, PartialEq, Debug, Copy, Clone)]
+pub struct LandingEvent {
+    pub ent: Entity,
+    /// what it landed on
+    pub ground: Entity,
+    pub strength: f32,
+}
+
 /// coyote timer
 #[derive(Component, Clone)]
 pub struct CoyoteTimer(pub Timer);
@@ -115,29 +125,29 @@ pub fn ground_detection(
                 }
             }
             CollisionEvent::Stopped(e1, e2, _) => {
-                if collidables.contains(*e1) {
-                    if let Ok(mut sensor) = ground_sensors.get_mut(*e2) {
-                        sensor.intersecting_ground_entities.remove(e1);
-                    }
-                } else if collidables.contains(*e2) {
-                    if let Ok(mut sensor) = ground_sensors.get_mut(*e1) {
-                        sensor.intersecting_ground_entities.remove(e2);
-                    }
+                // the ground may have just been despawned, like a broken
+                // block's wall collider, so don't check that it's collidable
+                if let Ok(mut sensor) = ground_sensors.get_mut(*e2) {
+                    sensor.intersecting_ground_entities.remove(e1);
+                } else if let Ok(mut sensor) = ground_sensors.get_mut(*e1) {
+                    sensor.intersecting_ground_entities.remove(e2);
                 }
             }
         }
     }
 }
 
-/// update GroundDetection based on GroundSensor
+/// update GroundDetection based on GroundSensor, & send a LandingEvent
+/// whenever something lands
 pub fn update_on_ground(
-    mut ground_detectors: Query<(&mut GroundDetection, &mut CoyoteTimer)>,
+    mut landing_events: EventWriter<LandingEvent>,
+    mut ground_detectors: Query<(&mut GroundDetection, &mut CoyoteTimer, Option<&Velocity>)>,
     ground_sensors: Query<&GroundSensor, Changed<GroundSensor>>,
 ) {
     // for every sensor
     for sensor in &ground_sensors {
         // get the ground detection & coyote time for the sensor entity
-        if let Ok((mut ground_detection, mut coyote_timer)) =
+        if let Ok((mut ground_detection, mut coyote_timer, velocity)) =
             ground_detectors.get_mut(sensor.ground_detection_entity)
         {
             let old_on_ground = ground_detection.on_ground();
@@ -145,6 +155,17 @@ pub fn update_on_ground(
             // update ground detection
             if let Some(ground_ent) = sensor.intersecting_ground_entities.iter().next() {
                 *ground_detection = GroundDetection::OnGround(*ground_ent);
+
+                if !old_on_ground {
+                    // the ground sensor sticks out below the collider, so
+                    // it's still falling when the sensor first touches
+                    let strength = velocity.map_or(0., |velocity| (-velocity.linvel.y).max(0.));
+                    landing_events.send(LandingEvent {
+                        ent: sensor.ground_detection_entity,
+                        ground: *ground_ent,
+                        strength,
+                    });
+                }
             } else {
                 *ground_detection = GroundDetection::NotOnGround;
 
@@ -170,14 +191,16 @@ pub struct GroundDetectionPlugin;
 
 impl Plugin for GroundDetectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                spawn_ground_sensor,
-                ground_detection,
-                update_on_ground,
-                tick_coyote_timer,
-            ),
-        );
+        app.add_event::<LandingEvent>()
+            .add_systems(Update, spawn_ground_sensor)
+            .add_systems(
+                FixedUpdate,
+                (
+                    (ground_detection, update_on_ground)
+                        .chain()
+                        .in_set(GameplaySet::Sense),
+                    tick_coyote_timer.in_set(GameplaySet::Act),
+                ),
+            );
     }
 }
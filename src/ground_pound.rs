@@ -0,0 +1,18 @@
+use bevy::prelude::*;
+
+/// pressing down in the air slams the player into the ground, breaking
+/// [`crate::breakable::Breakable`] blocks & bouncing off enemies
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub enum GroundPounder {
+    #[default]
+    NotPounding,
+    Pounding,
+}
+impl GroundPounder {
+    pub fn is_pounding(&self) -> bool {
+        match self {
+            Self::NotPounding => false,
+            Self::Pounding => true,
+        }
+    }
+}
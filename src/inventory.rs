@@ -1,20 +1,26 @@
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
-use crate::player::Player;
+use crate::{
+    level_data::{FromEntityChecked, LevelDataError},
+    player::Player,
+};
 
 #[derive(Clone, Component, Debug, Eq, Default, PartialEq)]
 pub struct Inventory(Vec<String>);
 
-impl From<&EntityInstance> for Inventory {
-    fn from(entity_instance: &EntityInstance) -> Self {
-        Inventory(
-            entity_instance
-                .iter_enums_field("items")
-                .expect("items field should be correctly typed")
-                .cloned()
-                .collect(),
-        )
+impl FromEntityChecked for Inventory {
+    fn from_entity_checked(
+        entity_instance: &EntityInstance,
+        _: &LayerInstance,
+    ) -> (Self, Option<LevelDataError>) {
+        match entity_instance.iter_enums_field("items") {
+            Ok(items) => (Inventory(items.cloned().collect()), None),
+            Err(err) => (
+                Inventory::default(),
+                Some(LevelDataError::entity_field(entity_instance, err)),
+            ),
+        }
     }
 }
 
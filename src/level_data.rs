@@ -0,0 +1,260 @@
+//! Problems with the LDtk project found while the game is running. Rather than
+//! panicking, systems skip whatever they can't read & report why to
+//! [`LevelDataErrors`], so a typo in a level or a project that's mid-reload
+//! doesn't take the game down.
+
+use std::{error::Error, fmt};
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::{
+    ldtk::{ldtk_fields::LdtkFieldsError, Level},
+    prelude::*,
+};
+
+#[derive(PartialEq, Debug)]
+pub enum LevelDataError {
+    /// a spawned level isn't in the project
+    LevelNotFound { iid: String },
+    /// a level's field has a value the game doesn't understand
+    LevelField {
+        level: String,
+        field: &'static str,
+        message: String,
+    },
+    /// an entity's field is missing or has the wrong type
+    EntityField {
+        entity: String,
+        err: LdtkFieldsError,
+    },
+}
+
+impl LevelDataError {
+    pub fn entity_field(entity_instance: &EntityInstance, err: LdtkFieldsError) -> Self {
+        Self::EntityField {
+            entity: format!("{} at {}", entity_instance.identifier, entity_instance.px),
+            err,
+        }
+    }
+}
+
+impl fmt::Display for LevelDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LevelNotFound { iid } => write!(f, "level {iid} isn't in the project"),
+            Self::LevelField {
+                level,
+                field,
+                message,
+            } => write!(f, "{level}: field {field}: {message}"),
+            Self::EntityField { entity, err } => write!(f, "{entity}: {err}"),
+        }
+    }
+}
+
+impl Error for LevelDataError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::EntityField { err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// the loaded project, or `None` while it's loading or the world is respawning
+pub fn loaded_project<'a>(
+    ldtk_projects: &Query<&LdtkProjectHandle>,
+    ldtk_project_assets: &'a Assets<LdtkProject>,
+) -> Option<&'a LdtkProject> {
+    ldtk_project_assets.get(ldtk_projects.get_single().ok()?)
+}
+
+/// the raw level for a spawned level
+pub fn raw_level<'a>(
+    ldtk_project: &'a LdtkProject,
+    level_iid: &LevelIid,
+) -> Result<&'a Level, LevelDataError> {
+    ldtk_project
+        .get_raw_level_by_iid(level_iid.get())
+        .ok_or_else(|| LevelDataError::LevelNotFound {
+            iid: level_iid.to_string(),
+        })
+}
+
+/// Every problem found in the project since it was last loaded
+#[derive(Resource, Default, Debug)]
+pub struct LevelDataErrors(pub Vec<LevelDataError>);
+
+/// Report a problem with the project. It's logged & listed in the error
+/// overlay, unless it's already been reported, so systems that run every frame
+/// don't flood the log or rebuild the overlay
+pub fn report(errors: &mut ResMut<LevelDataErrors>, err: LevelDataError) {
+    if !errors.bypass_change_detection().0.contains(&err) {
+        error!("{err}");
+        errors.0.push(err);
+    }
+}
+
+/// Problems found while building an entity's components from LDtk. Those
+/// impls don't have access to the world, so the problems are put on the entity
+/// & moved into the app's [`LevelDataErrors`]
+#[derive(Component, Default, Debug)]
+pub struct EntityDataErrors(pub Vec<LevelDataError>);
+
+/// A component built from an LDtk entity, which reports what was wrong with
+/// the entity instead of panicking
+pub trait FromEntityChecked: Sized {
+    fn from_entity_checked(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+    ) -> (Self, Option<LevelDataError>);
+}
+
+/// Use as an `#[ldtk_entity]` field to build `T` with its
+/// [`FromEntityChecked`] impl & report any problems
+#[derive(Default, Bundle)]
+pub struct Checked<T: Component> {
+    pub component: T,
+    pub errors: EntityDataErrors,
+}
+
+impl<T: Component + FromEntityChecked> LdtkEntity for Checked<T> {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        layer_instance: &LayerInstance,
+        _: Option<&Handle<Image>>,
+        _: Option<&TilesetDefinition>,
+        _: &AssetServer,
+        _: &mut Assets<TextureAtlasLayout>,
+    ) -> Self {
+        let (component, err) = T::from_entity_checked(entity_instance, layer_instance);
+        Self {
+            component,
+            errors: EntityDataErrors(err.into_iter().collect()),
+        }
+    }
+}
+
+/// move the problems found while spawning entities into [`LevelDataErrors`]
+fn collect_entity_errors(
+    mut commands: Commands,
+    mut errors: ResMut<LevelDataErrors>,
+    mut entities: Query<(Entity, &mut EntityDataErrors)>,
+) {
+    for (entity, mut entity_errors) in &mut entities {
+        for err in entity_errors.0.drain(..) {
+            report(&mut errors, err);
+        }
+        commands.entity(entity).remove::<EntityDataErrors>();
+    }
+}
+
+/// the project's been reloaded, so any problems might've been fixed. If not,
+/// they'll be reported again as the levels respawn
+fn clear_errors_on_reload(
+    mut asset_events: EventReader<AssetEvent<LdtkProject>>,
+    mut errors: ResMut<LevelDataErrors>,
+) {
+    if asset_events
+        .read()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }))
+    {
+        errors.0.clear();
+    }
+}
+
+#[cfg(feature = "dev")]
+#[derive(Component)]
+struct ErrorOverlay;
+
+/// list the errors in the corner of the screen, so broken levels are obvious
+/// while editing them
+#[cfg(feature = "dev")]
+fn update_error_overlay(
+    mut commands: Commands,
+    errors: Res<LevelDataErrors>,
+    overlays: Query<Entity, With<ErrorOverlay>>,
+) {
+    if !errors.is_changed() {
+        return;
+    }
+    for overlay in &overlays {
+        commands.entity(overlay).despawn_recursive();
+    }
+    if errors.0.is_empty() {
+        return;
+    }
+
+    let mut text = String::from("level data errors:");
+    for err in &errors.0 {
+        text.push_str(&format!("\n{err}"));
+    }
+    commands.spawn((
+        ErrorOverlay,
+        Text::new(text),
+        TextFont {
+            font_size: 12.,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        BackgroundColor(Color::srgba(0.5, 0., 0., 0.8)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(4.),
+            left: Val::Px(4.),
+            padding: UiRect::all(Val::Px(4.)),
+            ..default()
+        },
+    ));
+}
+
+pub struct LevelDataPlugin;
+
+impl Plugin for LevelDataPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelDataErrors>().add_systems(
+            Last,
+            (clear_errors_on_reload, collect_entity_errors).chain(),
+        );
+        #[cfg(feature = "dev")]
+        app.add_systems(Last, update_error_overlay.after(collect_entity_errors));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn level_not_found(iid: &str) -> LevelDataError {
+        LevelDataError::LevelNotFound {
+            iid: iid.to_string(),
+        }
+    }
+
+    #[test]
+    fn entity_errors_are_collected_into_their_own_app() {
+        let mut app = App::new();
+        app.init_resource::<LevelDataErrors>();
+        let entity = app
+            .world_mut()
+            .spawn(EntityDataErrors(vec![
+                level_not_found("a"),
+                level_not_found("a"),
+            ]))
+            .id();
+        let mut other_app = App::new();
+        other_app.init_resource::<LevelDataErrors>();
+
+        app.world_mut()
+            .run_system_once(collect_entity_errors)
+            .unwrap();
+
+        assert_eq!(
+            app.world().resource::<LevelDataErrors>().0,
+            [level_not_found("a")]
+        );
+        assert!(app.world().get::<EntityDataErrors>(entity).is_none());
+        assert!(other_app.world().resource::<LevelDataErrors>().0.is_empty());
+    }
+}
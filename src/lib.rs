@@ -1,4 +1,47 @@
-use bevy::prelude::*;
+use bevy::{app::PluginGroupBuilder, prelude::*};
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+pub mod actions;
+pub mod animation;
+pub mod animation_controller;
+pub mod audio;
+pub mod breakable;
+pub mod camera;
+pub mod camera_shake;
+pub mod camera_zone;
+pub mod climbing;
+pub mod colliders;
+pub mod controls;
+pub mod enemy;
+pub mod fixed_step;
+pub mod game_flow;
+pub mod ground_detection;
+pub mod ground_pound;
+pub mod inventory;
+pub mod jumping;
+pub mod level_data;
+pub mod lint;
+pub mod misc_objects;
+pub mod obstacle;
+pub mod parallax;
+pub mod platform;
+pub mod player;
+pub mod registry;
+pub mod replay;
+pub mod resolution;
+pub mod room_transition;
+pub mod save;
+pub mod spike;
+pub mod surface;
+#[cfg(test)]
+mod test_harness;
+pub mod timer_helpers;
+pub mod walls;
+
+#[cfg(all(feature = "dev", feature = "release"))]
+compile_error!("the dev & release features can't be used together, build releases with `--release --features release`");
 
 #[derive(Clone, Eq, PartialEq, Debug, Hash, Default, States)]
 pub enum GameState {
@@ -6,3 +49,57 @@ pub enum GameState {
     #[default]
     Playing,
 }
+
+/// Everything the game needs on top of bevy's `DefaultPlugins`: LDtk, physics,
+/// input & every gameplay plugin. Each plugin can be turned off on its own,
+/// e.g. for a tool that doesn't need enemies:
+///
+/// ```ignore
+/// app.add_plugins(FrijoleroPlugins.build().disable::<enemy::EnemyPlugin>())
+/// ```
+///
+/// The game also needs a [`game_flow::GameFile`] resource with the project to
+/// load.
+pub struct FrijoleroPlugins;
+
+impl PluginGroup for FrijoleroPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            // engine
+            .add(InputManagerPlugin::<actions::PlatformerAction>::default())
+            .add(fixed_step::FixedStepPlugin)
+            .add(LdtkPlugin)
+            .add(registry::RegistryPlugin)
+            .add(level_data::LevelDataPlugin)
+            .add(
+                RapierPhysicsPlugin::<colliders::ContactHooks>::pixels_per_meter(100.0)
+                    .in_fixed_schedule(),
+            )
+            // input
+            .add(controls::ControlsPlugin)
+            .add(replay::ReplayPlugin)
+            // gameplay
+            .add(game_flow::GameFlowPlugin)
+            .add(animation::SpriteAnimationPlugin)
+            .add(animation_controller::AnimationControllerPlugin)
+            .add(audio::GameAudioPlugin)
+            .add(player::PlayerPlugin)
+            .add(ground_detection::GroundDetectionPlugin)
+            .add(walls::WallsPlugin)
+            .add(breakable::BreakablePlugin)
+            .add(save::SavePlugin)
+            .add(climbing::ClimbingPlugin)
+            .add(enemy::EnemyPlugin)
+            .add(obstacle::ObstaclePlugin)
+            .add(platform::PlatformPlugin)
+            .add(room_transition::RoomTransitionPlugin)
+            .add(spike::SpikePlugin)
+            .add(misc_objects::MiscObjectsPlugin)
+            // presentation
+            .add(camera::CameraPlugin)
+            .add(camera_shake::CameraShakePlugin)
+            .add(camera_zone::CameraZonePlugin)
+            .add(resolution::ResolutionPlugin)
+            .add(parallax::ParallaxPlugin)
+    }
+}
@@ -0,0 +1,569 @@
+//! Checks an LDtk project for mistakes that would otherwise only show up when
+//! the game runs into them, like a platform missing its `speed` field. Used by
+//! the `frijolero-lint` binary.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_ecs_ldtk::{
+    ldtk::{EntityInstance, FieldInstance, FieldValue, LayerInstance, LdtkJson, Level, Type},
+    prelude::LdtkFields,
+};
+
+use crate::{
+    animation_controller::ANIMATION_FIELD,
+    audio::MUSIC_FIELD,
+    game_flow::CONNECTS_DOWN_FIELD,
+    parallax::{PARALLAX_FACTORS_FIELD, PARALLAX_IMAGES_FIELD},
+    registry::{EntityKind, IntCellKind, Registry, RegistryError},
+    walls::{WallDecomposition, WALL_COLLIDERS_FIELD},
+};
+
+/// the LDtk field types the game reads
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+enum FieldType {
+    Int,
+    Float,
+    Bool,
+    String,
+    FilePath,
+    Points,
+    Floats,
+    FilePaths,
+    Enums,
+}
+
+impl FieldType {
+    fn matches(self, value: &FieldValue) -> bool {
+        matches!(
+            (self, value),
+            (Self::Int, FieldValue::Int(_))
+                | (Self::Float, FieldValue::Float(_))
+                | (Self::Bool, FieldValue::Bool(_))
+                | (Self::String, FieldValue::String(_))
+                | (Self::FilePath, FieldValue::FilePath(_))
+                | (Self::Points, FieldValue::Points(_))
+                | (Self::Floats, FieldValue::Floats(_))
+                | (Self::FilePaths, FieldValue::FilePaths(_))
+                | (Self::Enums, FieldValue::Enums(_))
+        )
+    }
+
+    /// what LDtk calls the type
+    fn name(self) -> &'static str {
+        match self {
+            Self::Int => "Int",
+            Self::Float => "Float",
+            Self::Bool => "Bool",
+            Self::String => "String",
+            Self::FilePath => "FilePath",
+            Self::Points => "Array<Point>",
+            Self::Floats => "Array<Float>",
+            Self::FilePaths => "Array<FilePath>",
+            Self::Enums => "Array<Enum>",
+        }
+    }
+}
+
+/// a field the game reads from an entity or level
+struct FieldRule {
+    name: &'static str,
+    field_type: FieldType,
+    /// the game panics without it, rather than falling back to a default
+    required: bool,
+}
+
+const fn required(name: &'static str, field_type: FieldType) -> FieldRule {
+    FieldRule {
+        name,
+        field_type,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, field_type: FieldType) -> FieldRule {
+    FieldRule {
+        name,
+        field_type,
+        required: false,
+    }
+}
+
+/// every kind of entity the game spawns, & the fields it reads from them
+const ENTITY_RULES: &[(EntityKind, &[FieldRule])] = &[
+    (
+        EntityKind::Player,
+        &[
+            required("items", FieldType::Enums),
+            optional(ANIMATION_FIELD, FieldType::FilePath),
+        ],
+    ),
+    (EntityKind::PlayerRespawn, &[]),
+    (
+        EntityKind::Mob,
+        &[
+            required("patrol", FieldType::Points),
+            optional(ANIMATION_FIELD, FieldType::FilePath),
+        ],
+    ),
+    (
+        EntityKind::Obstacle,
+        &[
+            required("patrol", FieldType::Points),
+            optional(ANIMATION_FIELD, FieldType::FilePath),
+        ],
+    ),
+    (
+        EntityKind::Platform,
+        &[
+            required("patrol", FieldType::Points),
+            required("speed", FieldType::Int),
+            optional(ANIMATION_FIELD, FieldType::FilePath),
+        ],
+    ),
+    (
+        EntityKind::Chest,
+        &[optional(ANIMATION_FIELD, FieldType::FilePath)],
+    ),
+    (
+        EntityKind::Pumpkins,
+        &[optional(ANIMATION_FIELD, FieldType::FilePath)],
+    ),
+    (
+        EntityKind::CameraZone,
+        &[
+            optional("zoom", FieldType::Float),
+            optional("lock_x", FieldType::Bool),
+            optional("lock_y", FieldType::Bool),
+        ],
+    ),
+];
+
+/// the fields the game reads from levels
+const LEVEL_RULES: &[FieldRule] = &[
+    optional(MUSIC_FIELD, FieldType::FilePath),
+    optional(CONNECTS_DOWN_FIELD, FieldType::Bool),
+    optional(PARALLAX_IMAGES_FIELD, FieldType::FilePaths),
+    optional(PARALLAX_FACTORS_FIELD, FieldType::Floats),
+    optional(WALL_COLLIDERS_FIELD, FieldType::String),
+];
+
+/// entities with patrols walk between their points, so the points have to be
+/// somewhere they can get to
+const PATROLLING_ENTITIES: &[EntityKind] =
+    &[EntityKind::Mob, EntityKind::Obstacle, EntityKind::Platform];
+
+/// A mistake found in a project, & where it is
+#[derive(Clone, PartialEq, Debug)]
+pub struct LintIssue {
+    pub level: String,
+    /// the entity the issue is on, with its position so it can be found in
+    /// the editor
+    pub entity: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.entity {
+            Some(entity) => write!(f, "{}: {}: {}", self.level, entity, self.message),
+            None => write!(f, "{}: {}", self.level, self.message),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LintLoadError {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+    Registry(RegistryError),
+}
+impl fmt::Display for LintLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "could not read {path:?}: {err}"),
+            Self::Json(path, err) => write!(f, "could not parse {path:?}: {err}"),
+            Self::Registry(err) => write!(f, "{err}"),
+        }
+    }
+}
+impl std::error::Error for LintLoadError {}
+impl From<RegistryError> for LintLoadError {
+    fn from(err: RegistryError) -> Self {
+        Self::Registry(err)
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, LintLoadError> {
+    let bytes = fs::read(path).map_err(|err| LintLoadError::Io(path.to_path_buf(), err))?;
+    serde_json::from_slice(&bytes).map_err(|err| LintLoadError::Json(path.to_path_buf(), err))
+}
+
+/// load a project, every level in it, including levels saved in separate
+/// files, & its registry
+pub fn load_project(path: &Path) -> Result<(LdtkJson, Vec<Level>, Registry), LintLoadError> {
+    let ldtk_json: LdtkJson = read_json(path)?;
+    let project_dir = path.parent().unwrap_or(Path::new(""));
+    let levels = ldtk_json
+        .worlds
+        .iter()
+        .flat_map(|world| &world.levels)
+        .chain(&ldtk_json.levels)
+        .map(|level| match &level.external_rel_path {
+            Some(rel_path) if level.layer_instances.is_none() => {
+                read_json(&project_dir.join(rel_path))
+            }
+            _ => Ok(level.clone()),
+        })
+        .collect::<Result<_, _>>()?;
+    let registry = Registry::load_for_project(path)?;
+    Ok((ldtk_json, levels, registry))
+}
+
+/// check the fields against the rules, ignoring fields the game doesn't read
+fn lint_fields(fields: &[FieldInstance], rules: &[FieldRule]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for rule in rules {
+        let Some(field) = fields.iter().find(|field| field.identifier == rule.name) else {
+            if rule.required {
+                problems.push(format!(
+                    "missing field {} ({})",
+                    rule.name,
+                    rule.field_type.name()
+                ));
+            }
+            continue;
+        };
+        if !rule.field_type.matches(&field.value) {
+            problems.push(format!(
+                "field {} should be {}, but it's {}",
+                rule.name,
+                rule.field_type.name(),
+                field.field_instance_type
+            ));
+        } else if rule.required && matches!(field.value, FieldValue::Int(None)) {
+            problems.push(format!("field {} has no value", rule.name));
+        }
+    }
+    problems
+}
+
+/// the IntGrid value at a pixel position in the level, if there's an IntGrid
+/// layer there
+fn int_cell_at(level: &Level, px: IVec2) -> Option<i32> {
+    level
+        .layer_instances
+        .iter()
+        .flatten()
+        .filter(|layer| layer.layer_instance_type == Type::IntGrid)
+        .find_map(|layer| {
+            let cell = px / layer.grid_size;
+            let in_bounds = px.x >= 0 && px.y >= 0 && cell.x < layer.c_wid && cell.y < layer.c_hei;
+            in_bounds
+                .then(|| layer.int_grid_csv[(cell.y * layer.c_wid + cell.x) as usize])
+                .filter(|value| *value != 0)
+        })
+}
+
+/// patrol points outside the level or inside walls
+fn lint_patrol(
+    level: &Level,
+    layer: &LayerInstance,
+    entity: &EntityInstance,
+    registry: &Registry,
+) -> Vec<String> {
+    let Some(FieldValue::Points(points)) = entity
+        .field_instances
+        .iter()
+        .find(|field| field.identifier == "patrol")
+        .map(|field| &field.value)
+    else {
+        return Vec::new();
+    };
+
+    let mut problems = Vec::new();
+    for point in points.iter().flatten() {
+        let in_level =
+            point.x >= 0 && point.y >= 0 && point.x < layer.c_wid && point.y < layer.c_hei;
+        // the game moves the entity to the middle of the point's cell
+        let px = *point * layer.grid_size + IVec2::splat(layer.grid_size / 2);
+        if !in_level {
+            problems.push(format!("patrol point {point} is outside the level"));
+        } else if int_cell_at(level, px).is_some_and(|value| {
+            matches!(
+                registry.int_cell_kind(value),
+                Some(IntCellKind::Wall | IntCellKind::Breakable)
+            )
+        }) {
+            problems.push(format!("patrol point {point} is inside a wall"));
+        }
+    }
+    problems
+}
+
+/// Find everything wrong with the project's levels. The project is only
+/// used for its definitions, so levels can come from separate files
+pub fn lint_project(ldtk_json: &LdtkJson, levels: &[Level], registry: &Registry) -> Vec<LintIssue> {
+    let int_grid_names: HashMap<(i32, i32), &str> = ldtk_json
+        .defs
+        .layers
+        .iter()
+        .flat_map(|layer| {
+            layer
+                .int_grid_values
+                .iter()
+                .filter_map(|value| Some(((layer.uid, value.value), value.identifier.as_deref()?)))
+        })
+        .collect();
+
+    let mut issues = Vec::new();
+    for level in levels {
+        let mut issue = |entity: Option<String>, message: String| {
+            issues.push(LintIssue {
+                level: level.identifier.clone(),
+                entity,
+                message,
+            })
+        };
+
+        for problem in lint_fields(&level.field_instances, LEVEL_RULES) {
+            issue(None, problem);
+        }
+        if let Ok(Some(value)) = level.get_maybe_string_field(WALL_COLLIDERS_FIELD) {
+            if WallDecomposition::from_field(value).is_none() {
+                issue(
+                    None,
+                    format!("field {WALL_COLLIDERS_FIELD} should be plates or rectangles, but it's {value:?}"),
+                );
+            }
+        }
+
+        let mut has_respawn = false;
+        let mut unregistered_int_cells = Vec::new();
+        for layer in level.layer_instances.iter().flatten() {
+            for entity in &layer.entity_instances {
+                let name = Some(format!("{} at {}", entity.identifier, entity.px));
+                let Some(kind) = registry.entity_kind(&entity.identifier) else {
+                    issue(name, "unknown entity, the game won't spawn it".to_string());
+                    continue;
+                };
+                has_respawn |= kind == EntityKind::PlayerRespawn;
+                let rules = ENTITY_RULES
+                    .iter()
+                    .find(|(rules_kind, _)| *rules_kind == kind)
+                    .map_or(&[][..], |(_, rules)| rules);
+                let mut problems = lint_fields(&entity.field_instances, rules);
+                if PATROLLING_ENTITIES.contains(&kind) {
+                    problems.extend(lint_patrol(level, layer, entity, registry));
+                }
+                for problem in problems {
+                    issue(name.clone(), problem);
+                }
+            }
+
+            for &value in &layer.int_grid_csv {
+                if value != 0
+                    && registry.int_cell_kind(value).is_none()
+                    && !unregistered_int_cells.contains(&(layer.layer_def_uid, value))
+                {
+                    unregistered_int_cells.push((layer.layer_def_uid, value));
+                }
+            }
+        }
+
+        for (layer_def_uid, value) in unregistered_int_cells {
+            let message = match int_grid_names.get(&(layer_def_uid, value)) {
+                Some(name) => format!("IntGrid value {value} ({name}) does nothing in the game"),
+                None => format!("IntGrid value {value} does nothing in the game"),
+            };
+            issue(None, message);
+        }
+        if !has_respawn {
+            issue(
+                None,
+                "no Player_Respawn, the player can't respawn here".to_string(),
+            );
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs_ldtk::ldtk::{IntGridValueDefinition, LayerDefinition};
+
+    use super::*;
+
+    fn field(identifier: &str, field_instance_type: &str, value: FieldValue) -> FieldInstance {
+        FieldInstance {
+            identifier: identifier.to_string(),
+            tile: None,
+            field_instance_type: field_instance_type.to_string(),
+            value,
+            def_uid: 0,
+            real_editor_values: Vec::new(),
+        }
+    }
+
+    fn entity(identifier: &str, fields: Vec<FieldInstance>) -> EntityInstance {
+        EntityInstance {
+            identifier: identifier.to_string(),
+            px: IVec2::new(16, 32),
+            field_instances: fields,
+            ..default()
+        }
+    }
+
+    fn patrol(points: &[IVec2]) -> FieldInstance {
+        let points = points.iter().copied().map(Some).collect();
+        field("patrol", "Array<Point>", FieldValue::Points(points))
+    }
+
+    /// a 4x4 level with these entities & IntGrid values, besides a
+    /// Player_Respawn
+    fn level(entities: Vec<EntityInstance>, int_grid_csv: Vec<i32>) -> Level {
+        let layer = |layer_instance_type| LayerInstance {
+            layer_instance_type,
+            grid_size: 16,
+            c_wid: 4,
+            c_hei: 4,
+            layer_def_uid: 1,
+            ..default()
+        };
+        let mut entity_instances = vec![entity("Player_Respawn", Vec::new())];
+        entity_instances.extend(entities);
+        Level {
+            identifier: "Level_0".to_string(),
+            layer_instances: Some(vec![
+                LayerInstance {
+                    entity_instances,
+                    ..layer(Type::Entities)
+                },
+                LayerInstance {
+                    int_grid_csv,
+                    ..layer(Type::IntGrid)
+                },
+            ]),
+            ..default()
+        }
+    }
+
+    fn lint(ldtk_json: &LdtkJson, level: Level) -> Vec<String> {
+        lint_project(ldtk_json, &[level], &Registry::default())
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn a_level_with_nothing_wrong_has_no_issues() {
+        assert_eq!(
+            lint(&LdtkJson::default(), level(Vec::new(), vec![0; 16])),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn missing_fields_are_found() {
+        let platform = entity("Platform", vec![patrol(&[IVec2::ZERO])]);
+        assert_eq!(
+            lint(&LdtkJson::default(), level(vec![platform], vec![0; 16])),
+            ["Level_0: Platform at [16, 32]: missing field speed (Int)"]
+        );
+    }
+
+    #[test]
+    fn mistyped_fields_are_found() {
+        let platform = entity(
+            "Platform",
+            vec![
+                patrol(&[IVec2::ZERO]),
+                field("speed", "Float", FieldValue::Float(Some(1.))),
+            ],
+        );
+        let mut level = level(vec![platform], vec![0; 16]);
+        level.field_instances.push(field(
+            WALL_COLLIDERS_FIELD,
+            "String",
+            FieldValue::String(Some("outline".to_string())),
+        ));
+        assert_eq!(
+            lint(&LdtkJson::default(), level),
+            [
+                "Level_0: field wall_colliders should be plates or rectangles, but it's \"outline\"",
+                "Level_0: Platform at [16, 32]: field speed should be Int, but it's Float",
+            ]
+        );
+    }
+
+    #[test]
+    fn unregistered_entities_are_found() {
+        let ghost = entity("Ghost", Vec::new());
+        assert_eq!(
+            lint(&LdtkJson::default(), level(vec![ghost], vec![0; 16])),
+            ["Level_0: Ghost at [16, 32]: unknown entity, the game won't spawn it"]
+        );
+    }
+
+    #[test]
+    fn levels_without_a_respawn_are_found() {
+        let mut level = level(Vec::new(), vec![0; 16]);
+        level.layer_instances.as_mut().unwrap()[0]
+            .entity_instances
+            .clear();
+        assert_eq!(
+            lint(&LdtkJson::default(), level),
+            ["Level_0: no Player_Respawn, the player can't respawn here"]
+        );
+    }
+
+    #[test]
+    fn unregistered_int_grid_values_are_found_once() {
+        let mut ldtk_json = LdtkJson::default();
+        ldtk_json.defs.layers.push(LayerDefinition {
+            uid: 1,
+            int_grid_values: vec![IntGridValueDefinition {
+                value: 9,
+                identifier: Some("Lava".to_string()),
+                ..default()
+            }],
+            ..default()
+        });
+        let mut int_grid_csv = vec![0; 16];
+        int_grid_csv[0] = 9;
+        int_grid_csv[1] = 9;
+        int_grid_csv[2] = 8;
+        assert_eq!(
+            lint(&ldtk_json, level(Vec::new(), int_grid_csv)),
+            [
+                "Level_0: IntGrid value 9 (Lava) does nothing in the game",
+                "Level_0: IntGrid value 8 does nothing in the game",
+            ]
+        );
+    }
+
+    #[test]
+    fn patrol_points_outside_the_level_are_found() {
+        let mob = entity("Mob", vec![patrol(&[IVec2::ZERO, IVec2::new(4, 1)])]);
+        assert_eq!(
+            lint(&LdtkJson::default(), level(vec![mob], vec![0; 16])),
+            ["Level_0: Mob at [16, 32]: patrol point [4, 1] is outside the level"]
+        );
+    }
+
+    #[test]
+    fn patrol_points_inside_walls_are_found() {
+        let mut int_grid_csv = vec![0; 16];
+        // the wall's at [1, 2], the ladder's at [2, 2]
+        int_grid_csv[9] = 1;
+        int_grid_csv[10] = 4;
+        let mob = entity("Mob", vec![patrol(&[IVec2::new(1, 2), IVec2::new(2, 2)])]);
+        assert_eq!(
+            lint(&LdtkJson::default(), level(vec![mob], int_grid_csv)),
+            ["Level_0: Mob at [16, 32]: patrol point [1, 2] is inside a wall"]
+        );
+    }
+}
@@ -1,52 +1,97 @@
-use std::{env, path::Path};
+use std::{env, process, time::Duration};
 
-use bevy::prelude::*;
+#[cfg(feature = "dev")]
+use bean_platformer::inventory;
+use bean_platformer::{
+    camera_shake::CameraShake, game_flow::GameFile, replay::ExitAfterReplay, FrijoleroPlugins,
+};
+#[cfg(feature = "release")]
+use bevy::log::{Level, LogPlugin};
+use bevy::{
+    app::ScheduleRunnerPlugin,
+    asset::AssetMetaCheck,
+    prelude::*,
+    window::{ExitCondition, WindowResolution},
+    winit::WinitPlugin,
+};
 use bevy_ecs_ldtk::prelude::*;
+#[cfg(feature = "dev")]
 use bevy_rapier2d::prelude::*;
-use game_flow::GameFile;
-use leafwing_input_manager::prelude::*;
+use cli::Args;
 
-mod actions;
-mod camera;
-mod climbing;
-mod colliders;
-mod enemy;
-mod game_flow;
-mod ground_detection;
-mod inventory;
-mod jumping;
-mod misc_objects;
-mod obstacle;
-mod platform;
-mod player;
-mod spike;
-mod timer_helpers;
-mod walls;
+mod cli;
+
+/// how often the game updates when there's no window to drive it
+const HEADLESS_FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
 
 fn main() {
-    let game_path_string: String = env::args()
-        .nth(1)
-        .unwrap_or("bean_platformer.ldtk".to_string());
-    let game_path = Path::new(&game_path_string).to_path_buf();
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n\n{}", cli::USAGE);
+            process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    if args.list_levels {
+        if let Err(err) = cli::list_levels(&args.project) {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+        return;
+    }
 
-    App::new()
-        .add_plugins(
-            DefaultPlugins
-                .set(ImagePlugin::default_nearest())
-                .set(AssetPlugin {
-                    // TODO(prod): Disable on prod
-                    watch_for_changes_override: None,
-                    ..Default::default()
+    let window_plugin = if args.headless {
+        WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            ..default()
+        }
+    } else {
+        WindowPlugin {
+            primary_window: Some(Window {
+                resolution: args.window_size.map_or_else(default, |size| {
+                    WindowResolution::new(size.x as f32, size.y as f32)
                 }),
-        )
-        .add_plugins(InputManagerPlugin::<actions::PlatformerAction>::default())
-        .add_plugins((
-            LdtkPlugin,
-            RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0),
-            // TODO(prod): Disable on prod
-            RapierDebugRenderPlugin::default(),
-        ))
-        .insert_resource(GameFile { path: game_path })
+                ..default()
+            }),
+            ..default()
+        }
+    };
+    let mut default_plugins = DefaultPlugins
+        .set(ImagePlugin::default_nearest())
+        .set(AssetPlugin {
+            watch_for_changes_override: Some(cfg!(feature = "dev")),
+            // shipped assets don't come with `.meta` files
+            meta_check: if cfg!(feature = "release") {
+                AssetMetaCheck::Never
+            } else {
+                AssetMetaCheck::default()
+            },
+            ..Default::default()
+        })
+        .set(window_plugin);
+    // players only need to see what's gone wrong
+    #[cfg(feature = "release")]
+    {
+        default_plugins = default_plugins.set(LogPlugin {
+            level: Level::WARN,
+            ..default()
+        });
+    }
+    if args.headless {
+        default_plugins = default_plugins.disable::<WinitPlugin>();
+    }
+
+    let mut app = App::new();
+    app.add_plugins(default_plugins)
+        .add_plugins(FrijoleroPlugins)
+        .insert_resource(args.replay_mode)
+        .insert_resource(args.player_start)
+        .insert_resource(GameFile { path: args.project })
         .insert_resource(LevelSelection::Uid(0))
         .insert_resource(LdtkSettings {
             level_spawn_behavior: LevelSpawnBehavior::UseWorldTranslation {
@@ -54,20 +99,17 @@ fn main() {
             },
             set_clear_color: SetClearColor::FromLevelBackground,
             ..Default::default()
-        })
-        .add_event::<game_flow::RespawnWorldEvent>()
-        .add_event::<game_flow::RespawnLevelEvent>()
-        .add_plugins(game_flow::GameFlowPlugin)
-        .add_plugins(player::PlayerPlugin)
-        .add_plugins(ground_detection::GroundDetectionPlugin)
-        .add_plugins(walls::WallsPlugin)
-        .add_plugins(climbing::ClimbingPlugin)
-        .add_plugins(enemy::EnemyPlugin)
-        .add_plugins(obstacle::ObstaclePlugin)
-        .add_plugins(platform::PlatformPlugin)
-        .add_plugins(spike::SpikePlugin)
-        .add_plugins(misc_objects::MiscObjectsPlugin)
-        .add_systems(Update, inventory::dbg_print_inventory)
-        .add_systems(Update, camera::camera_fit_inside_current_level)
-        .run();
+        });
+    if let Some(seed) = args.seed {
+        app.world_mut().resource_mut::<CameraShake>().seed = seed;
+    }
+    if args.headless {
+        app.add_plugins(ScheduleRunnerPlugin::run_loop(HEADLESS_FRAME_TIME))
+            .insert_resource(ExitAfterReplay);
+    }
+    // physics debug render & debug keys, left out of release builds
+    #[cfg(feature = "dev")]
+    app.add_plugins(RapierDebugRenderPlugin::default())
+        .add_systems(Update, inventory::dbg_print_inventory);
+    app.run();
 }
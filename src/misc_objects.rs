@@ -1,14 +1,28 @@
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 
-use crate::colliders::ColliderBundle;
+use crate::{
+    animation_controller::AnimationController,
+    audio::{GameSound, PlaySoundEvent},
+    colliders::ColliderBundle,
+    player::Player,
+    registry::{EntityKind, RegistryAppExt},
+};
+
+/// how close the player has to be for a chest to open
+const CHEST_OPEN_DISTANCE: f32 = 24.;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Chest;
 
 #[derive(Clone, Default, Bundle, LdtkEntity)]
 pub struct ChestBundle {
+    pub chest: Chest,
     #[sprite_sheet]
     pub sprite_sheet: Sprite,
-    #[from_entity_instance]
     pub collider_bundle: ColliderBundle,
+    #[with(AnimationController::chest)]
+    pub animation_controller: AnimationController,
 }
 
 #[derive(Clone, Default, Bundle, LdtkEntity)]
@@ -17,11 +31,36 @@ pub struct PumpkinsBundle {
     pub sprite_sheet: Sprite,
 }
 
+/// open chests while the player is next to them
+fn open_chests_near_player(
+    mut sound_event: EventWriter<PlaySoundEvent>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut chest_query: Query<(&GlobalTransform, &mut AnimationController), With<Chest>>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    for (chest_transform, mut controller) in &mut chest_query {
+        let near = chest_transform
+            .translation()
+            .truncate()
+            .distance(player_transform.translation().truncate())
+            < CHEST_OPEN_DISTANCE;
+        if controller.has_flag("open") != near {
+            controller.set_flag("open", near);
+            if near {
+                sound_event.send(PlaySoundEvent(GameSound::Pickup));
+            }
+        }
+    }
+}
+
 pub struct MiscObjectsPlugin;
 
 impl Plugin for MiscObjectsPlugin {
     fn build(&self, app: &mut App) {
-        app.register_ldtk_entity::<ChestBundle>("Chest")
-            .register_ldtk_entity::<PumpkinsBundle>("Pumpkins");
+        app.register_entity_kind::<ChestBundle>(EntityKind::Chest)
+            .register_entity_kind::<PumpkinsBundle>(EntityKind::Pumpkins)
+            .add_systems(Update, open_chests_near_player);
     }
 }
@@ -2,20 +2,28 @@ use bevy::prelude::*;
 use bevy_ecs_ldtk::{prelude::*, utils::ldtk_pixel_coords_to_translation_pivoted};
 use bevy_rapier2d::dynamics::Velocity;
 
-use crate::colliders::ColliderBundle;
+use crate::{
+    animation_controller::AnimationController,
+    colliders::ColliderBundle,
+    fixed_step::GameplaySet,
+    level_data::{Checked, FromEntityChecked, LevelDataError},
+    registry::{EntityKind, RegistryAppExt},
+};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Obstacle;
 
-#[derive(Clone, Default, Bundle, LdtkEntity)]
+#[derive(Default, Bundle, LdtkEntity)]
 pub struct ObstacleBundle {
     #[sprite_sheet]
     pub sprite_sheet: Sprite,
-    #[from_entity_instance]
+    #[with(ColliderBundle::walker)]
     pub collider_bundle: ColliderBundle,
+    #[with(AnimationController::obstacle)]
+    pub animation_controller: AnimationController,
     pub obstacle: Obstacle,
     #[ldtk_entity]
-    pub patrol: Patrol,
+    pub patrol: Checked<Patrol>,
 }
 
 #[derive(Clone, PartialEq, Debug, Default, Component)]
@@ -25,15 +33,11 @@ pub struct Patrol {
     pub forward: bool,
 }
 
-impl LdtkEntity for Patrol {
-    fn bundle_entity(
+impl FromEntityChecked for Patrol {
+    fn from_entity_checked(
         entity_instance: &EntityInstance,
         layer_instance: &LayerInstance,
-        _: Option<&Handle<Image>>,
-        _: Option<&TilesetDefinition>,
-        _: &AssetServer,
-        _: &mut Assets<TextureAtlasLayout>,
-    ) -> Patrol {
+    ) -> (Patrol, Option<LevelDataError>) {
         let mut points = Vec::new();
         points.push(ldtk_pixel_coords_to_translation_pivoted(
             entity_instance.px,
@@ -42,9 +46,21 @@ impl LdtkEntity for Patrol {
             entity_instance.pivot,
         ));
 
-        let ldtk_patrol_points = entity_instance
-            .iter_points_field("patrol")
-            .expect("patrol field should be correclty typed, expected Array<Point>");
+        // without a patrol, stay where the entity was placed
+        let ldtk_patrol_points = match entity_instance.iter_points_field("patrol") {
+            Ok(ldtk_patrol_points) => ldtk_patrol_points,
+            Err(err) => {
+                let patrol = Patrol {
+                    points,
+                    index: 0,
+                    forward: true,
+                };
+                return (
+                    patrol,
+                    Some(LevelDataError::entity_field(entity_instance, err)),
+                );
+            }
+        };
 
         for ldtk_point in ldtk_patrol_points {
             // The +1 is necessary here due to the pivot of the entities in the sample
@@ -63,11 +79,12 @@ impl LdtkEntity for Patrol {
             ));
         }
 
-        Patrol {
+        let patrol = Patrol {
             points,
             index: 1,
             forward: true,
-        }
+        };
+        (patrol, None)
     }
 }
 
@@ -108,7 +125,7 @@ pub struct ObstaclePlugin;
 
 impl Plugin for ObstaclePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, patrol)
-            .register_ldtk_entity::<ObstacleBundle>("Obstacle");
+        app.add_systems(FixedUpdate, patrol.in_set(GameplaySet::Act))
+            .register_entity_kind::<ObstacleBundle>(EntityKind::Obstacle);
     }
 }
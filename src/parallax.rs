@@ -0,0 +1,210 @@
+use bevy::{
+    image::{ImageAddressMode, ImageLoaderSettings, ImageSampler, ImageSamplerDescriptor},
+    prelude::*,
+};
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::{camera::CameraController, game_flow::GameFile, level_data};
+
+// parallax constants
+
+/// Level field listing the images to draw behind the level, back to front.
+/// Paths are relative to the LDtk project file.
+pub(crate) const PARALLAX_IMAGES_FIELD: &str = "parallax_images";
+/// Level field listing how much each image moves with the level. 0 means it
+/// stays fixed to the camera, 1 means it scrolls with the level. If missing,
+/// layers further back move less.
+pub(crate) const PARALLAX_FACTORS_FIELD: &str = "parallax_factors";
+
+/// z of the back-most layer. bevy_ecs_ldtk puts the level background at 0 &
+/// the first layer at 1, so this slots in between them
+const PARALLAX_BASE_Z: f32 = 0.5;
+const PARALLAX_Z_STEP: f32 = 0.01;
+
+/// a background image that scrolls slower than the level, to look further
+/// away. Drawn as a child of its level and never drawn outside of it
+#[derive(Clone, PartialEq, Debug, Component)]
+pub struct ParallaxLayer {
+    pub image: Handle<Image>,
+    pub factor: f32,
+    level_size: Vec2,
+}
+
+/// when a level spawns, spawn its parallax layers from the level's fields
+fn spawn_parallax_layers(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelEvent>,
+    levels: Query<(Entity, &LevelIid)>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+    game_file: Res<GameFile>,
+    asset_server: Res<AssetServer>,
+) {
+    for level_event in level_events.read() {
+        let LevelEvent::Spawned(spawned_iid) = level_event else {
+            continue;
+        };
+        let Some(ldtk_project) = level_data::loaded_project(&ldtk_projects, &ldtk_project_assets)
+        else {
+            continue;
+        };
+        let Some(level) = ldtk_project.get_raw_level_by_iid(spawned_iid.get()) else {
+            continue;
+        };
+        let Ok(image_paths) = level.iter_file_paths_field(PARALLAX_IMAGES_FIELD) else {
+            continue;
+        };
+        let factors: Vec<f32> = level
+            .iter_floats_field(PARALLAX_FACTORS_FIELD)
+            .map(|factors| factors.copied().collect())
+            .unwrap_or_default();
+
+        let Some((level_ent, _)) = levels.iter().find(|(_, iid)| *iid == spawned_iid) else {
+            continue;
+        };
+
+        // Image paths are relative to the project file
+        let project_dir = game_file.path.parent().unwrap_or(&game_file.path);
+        let level_size = Vec2::new(level.px_wid as f32, level.px_hei as f32);
+        let image_paths: Vec<&String> = image_paths.collect();
+        let layer_count = image_paths.len();
+
+        commands.entity(level_ent).with_children(|level_builder| {
+            for (i, image_path) in image_paths.into_iter().enumerate() {
+                // Default to layers further back moving less
+                let factor = factors
+                    .get(i)
+                    .copied()
+                    .unwrap_or((i + 1) as f32 / (layer_count + 1) as f32);
+
+                // Repeat the image horizontally, so it can tile as it scrolls
+                let image: Handle<Image> = asset_server.load_with_settings(
+                    project_dir.join(image_path),
+                    |settings: &mut ImageLoaderSettings| {
+                        settings.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+                            address_mode_u: ImageAddressMode::Repeat,
+                            ..ImageSamplerDescriptor::nearest()
+                        });
+                    },
+                );
+
+                level_builder.spawn((
+                    ParallaxLayer {
+                        image: image.clone(),
+                        factor,
+                        level_size,
+                    },
+                    Sprite::from_image(image),
+                    Transform::from_xyz(0., 0., PARALLAX_BASE_Z + PARALLAX_Z_STEP * i as f32),
+                    Visibility::Hidden,
+                ));
+            }
+        });
+    }
+}
+
+/// How far into the image a layer's left edge is. The sprite's pinned to the
+/// level, so the image is scrolled back by the part of the camera's movement
+/// the layer shouldn't follow
+fn parallax_scroll(camera_x: f32, level_min_x: f32, factor: f32) -> f32 {
+    -(camera_x - level_min_x) * (1. - factor)
+}
+
+/// scroll the parallax layers based on where the camera is in their level.
+/// The sprite always covers the bottom of the level, and the part of the
+/// image it shows is offset to fake depth
+#[allow(clippy::type_complexity)]
+fn scroll_parallax_layers(
+    images: Res<Assets<Image>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<CameraController>>,
+    levels: Query<&GlobalTransform, With<LevelIid>>,
+    mut parallax_layers: Query<
+        (
+            &ParallaxLayer,
+            &Parent,
+            &mut Sprite,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        Without<CameraController>,
+    >,
+) {
+    let Ok((camera_transform, projection)) = camera_query.get_single() else {
+        return;
+    };
+    let camera_center = camera_transform.translation.truncate() + projection.area.center();
+
+    for (layer, parent, mut sprite, mut transform, mut visibility) in &mut parallax_layers {
+        let (Some(image), Ok(level_transform)) =
+            (images.get(&layer.image), levels.get(parent.get()))
+        else {
+            continue;
+        };
+        let image_size = image.size_f32();
+        let level_min = level_transform.translation().truncate();
+
+        // Show as much of the image as fits in the level, anchored to the
+        // bottom of the level
+        let height = image_size.y.min(layer.level_size.y);
+        let scroll = parallax_scroll(camera_center.x, level_min.x, layer.factor);
+        sprite.custom_size = Some(Vec2::new(layer.level_size.x, height));
+        sprite.rect = Some(Rect::new(
+            scroll,
+            image_size.y - height,
+            scroll + layer.level_size.x,
+            image_size.y,
+        ));
+        transform.translation.x = layer.level_size.x / 2.;
+        transform.translation.y = height / 2.;
+
+        *visibility = Visibility::Inherited;
+    }
+}
+
+/// draws per-level parallax backgrounds, configured with level fields in LDtk
+pub struct ParallaxPlugin;
+
+impl Plugin for ParallaxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (spawn_parallax_layers, scroll_parallax_layers));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL_MIN_X: f32 = 100.;
+
+    /// the x of the image texel drawn at this world x
+    fn texel_at(world_x: f32, camera_x: f32, factor: f32) -> f32 {
+        parallax_scroll(camera_x, LEVEL_MIN_X, factor) + world_x - LEVEL_MIN_X
+    }
+
+    #[test]
+    fn layers_with_factor_0_stay_fixed_to_the_camera() {
+        // the same point on screen, as the camera moves
+        let texels: Vec<f32> = [120., 200., 350.]
+            .into_iter()
+            .map(|camera_x| texel_at(camera_x + 10., camera_x, 0.))
+            .collect();
+        assert_eq!(texels, [texels[0]; 3]);
+    }
+
+    #[test]
+    fn layers_with_factor_1_move_with_the_level() {
+        // the same point in the level, as the camera moves
+        let texels: Vec<f32> = [120., 200., 350.]
+            .into_iter()
+            .map(|camera_x| texel_at(150., camera_x, 1.))
+            .collect();
+        assert_eq!(texels, [50.; 3]);
+    }
+
+    #[test]
+    fn layers_in_between_follow_the_camera_partway() {
+        let start = texel_at(130., 120., 0.5);
+        let moved = texel_at(140., 130., 0.5);
+        assert_eq!(moved - start, 5.);
+    }
+}
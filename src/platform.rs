@@ -2,20 +2,28 @@ use bevy::prelude::*;
 use bevy_ecs_ldtk::{prelude::*, utils::ldtk_pixel_coords_to_translation_pivoted};
 use bevy_rapier2d::dynamics::Velocity;
 
-use crate::colliders::ColliderBundle;
+use crate::{
+    animation_controller::AnimationController,
+    colliders::ColliderBundle,
+    fixed_step::GameplaySet,
+    level_data::{Checked, FromEntityChecked, LevelDataError},
+    registry::{EntityKind, RegistryAppExt},
+};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Platform;
 
-#[derive(Clone, Default, Bundle, LdtkEntity)]
+#[derive(Default, Bundle, LdtkEntity)]
 pub struct PlatformBundle {
     pub platform: Platform,
     #[sprite_sheet]
     pub sprite_sheet: Sprite,
-    #[from_entity_instance]
+    #[with(ColliderBundle::platform)]
     pub collider_bundle: ColliderBundle,
+    #[with(AnimationController::platform)]
+    pub animation_controller: AnimationController,
     #[ldtk_entity]
-    pub patrol: Patrol,
+    pub patrol: Checked<Patrol>,
 }
 
 #[derive(Clone, PartialEq, Debug, Default, Component)]
@@ -26,15 +34,11 @@ pub struct Patrol {
     pub forward: bool,
 }
 
-impl LdtkEntity for Patrol {
-    fn bundle_entity(
+impl FromEntityChecked for Patrol {
+    fn from_entity_checked(
         entity_instance: &EntityInstance,
         layer_instance: &LayerInstance,
-        _: Option<&Handle<Image>>,
-        _: Option<&TilesetDefinition>,
-        _: &AssetServer,
-        _: &mut Assets<TextureAtlasLayout>,
-    ) -> Patrol {
+    ) -> (Patrol, Option<LevelDataError>) {
         let mut points = Vec::new();
         points.push(ldtk_pixel_coords_to_translation_pivoted(
             entity_instance.px,
@@ -43,13 +47,25 @@ impl LdtkEntity for Patrol {
             entity_instance.pivot,
         ));
 
-        let ldtk_patrol_points = entity_instance
+        // without a patrol & speed, stay where the platform was placed
+        let fields = entity_instance
             .iter_points_field("patrol")
-            .expect("expected 'patrol' field on platform of type Array<Point>");
-
-        let ldtk_patrol_speed = entity_instance
-            .get_int_field("speed")
-            .expect("expected 'speed' field on platform of type Int");
+            .and_then(|points| Ok((points, entity_instance.get_int_field("speed")?)));
+        let (ldtk_patrol_points, ldtk_patrol_speed) = match fields {
+            Ok(fields) => fields,
+            Err(err) => {
+                let patrol = Patrol {
+                    points,
+                    speed: 0,
+                    index: 0,
+                    forward: true,
+                };
+                return (
+                    patrol,
+                    Some(LevelDataError::entity_field(entity_instance, err)),
+                );
+            }
+        };
 
         for ldtk_point in ldtk_patrol_points {
             // The +1 is necessary here due to the pivot of the entities in the sample
@@ -68,12 +84,13 @@ impl LdtkEntity for Patrol {
             ));
         }
 
-        Patrol {
+        let patrol = Patrol {
             points,
             speed: *ldtk_patrol_speed,
             index: 1,
             forward: true,
-        }
+        };
+        (patrol, None)
     }
 }
 
@@ -115,7 +132,7 @@ pub struct PlatformPlugin;
 
 impl Plugin for PlatformPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, patrol)
-            .register_ldtk_entity::<PlatformBundle>("Platform");
+        app.add_systems(FixedUpdate, patrol.in_set(GameplaySet::Act))
+            .register_entity_kind::<PlatformBundle>(EntityKind::Platform);
     }
 }
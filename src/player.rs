@@ -2,20 +2,31 @@ use std::{ops::DerefMut, time::Duration};
 
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
-use bevy_rapier2d::dynamics::Velocity;
+use bevy_rapier2d::prelude::{Collider, Velocity};
 use leafwing_input_manager::prelude::*;
 
+use crate::camera_shake::CameraShakeEvent;
+#[cfg(feature = "dev")]
 use crate::game_flow::{RespawnLevelEvent, RespawnWorldEvent};
+use crate::room_transition::not_transitioning;
 use crate::spike::SpikeDetection;
 use crate::timer_helpers::TimerHelper;
 use crate::{
     actions::PlatformerAction,
+    animation::SpriteAnimation,
+    breakable::BreakBlocksEvent,
     climbing::Climber,
     colliders::ColliderBundle,
-    ground_detection::{CoyoteTimer, GroundDetection},
+    enemy::Enemy,
+    fixed_step::GameplaySet,
+    ground_detection::{CoyoteTimer, GroundDetection, LandingEvent},
+    ground_pound::GroundPounder,
     inventory::Inventory,
     jumping::Jumper,
+    level_data::Checked,
     platform::Platform,
+    registry::{EntityKind, RegistryAppExt},
+    surface::Surface,
 };
 
 /// tag for players
@@ -23,11 +34,12 @@ use crate::{
 pub struct Player;
 
 /// player bundle, containing everything needed
-#[derive(Clone, Default, Bundle, LdtkEntity)]
+#[derive(Default, Bundle, LdtkEntity)]
 pub struct PlayerBundle {
     pub player: Player,
     pub climber: Climber,
     pub jumper: Jumper,
+    pub ground_pounder: GroundPounder,
     pub coyote_timer: CoyoteTimer,
     pub jump_buffer_timer: JumpBufferTimer,
 
@@ -40,12 +52,12 @@ pub struct PlayerBundle {
     #[worldly]
     pub worldly: Worldly,
 
-    #[from_entity_instance]
+    #[with(ColliderBundle::player)]
     pub collider_bundle: ColliderBundle,
 
-    // Build Items Component manually by using `impl From<&EntityInstance>`
-    #[from_entity_instance]
-    items: Inventory,
+    // Build Items Component manually by using `impl FromEntityChecked`
+    #[ldtk_entity]
+    items: Checked<Inventory>,
 
     // The whole EntityInstance can be stored directly as an EntityInstance component
     #[from_entity_instance]
@@ -59,11 +71,28 @@ pub struct PlayerBundle {
 const JUMP_VELOCITY: f32 = 400.;
 const RUN_VELOCITY: f32 = 150.;
 const CLIMB_VELOCITY: f32 = 150.;
+/// how quickly the player speeds up & slows down on ice, in pixels per second
+/// squared
+const ICE_ACCELERATION: f32 = 300.;
+/// how much of their running & jumping speed the player keeps on sticky ground
+const STICKY_SPEED_SCALE: f32 = 0.5;
+/// landing faster than this shakes the camera
+const HEAVY_LANDING_VELOCITY: f32 = 700.;
+/// how fast the player slams down while ground pounding. Slower than a heavy
+/// landing, so a ground pound only gets its own shake
+const GROUND_POUND_VELOCITY: f32 = 600.;
+/// how fast the player bounces up after ground pounding an enemy
+const GROUND_POUND_BOUNCE_VELOCITY: f32 = 350.;
+/// how far below the player's feet a ground pound breaks blocks, in pixels
+const GROUND_POUND_DEPTH: f32 = 4.;
 
 /// configure player movement
+#[allow(clippy::type_complexity)]
 pub fn player_movement(
+    time: Res<Time>,
     mut animation_event: EventWriter<AnimationEvent>,
     platforms_query: Query<(Entity, &Velocity), (With<Platform>, Without<Player>)>,
+    surfaces_query: Query<&Surface>,
     mut player_query: Query<
         (
             Entity,
@@ -71,6 +100,7 @@ pub fn player_movement(
             &mut Velocity,
             &mut Climber,
             &mut Jumper,
+            &mut GroundPounder,
             &mut CoyoteTimer,
             &mut JumpBufferTimer,
             &GroundDetection,
@@ -84,6 +114,7 @@ pub fn player_movement(
         mut velocity,
         mut climber,
         mut jumper,
+        mut ground_pounder,
         mut coyote_timer,
         mut jump_buffer_timer,
         ground_detection,
@@ -91,40 +122,83 @@ pub fn player_movement(
     {
         let on_ground = ground_detection.on_ground();
 
-        // if on a platform, get the platform's velocity
+        // handle ground pounding
+
+        // pressing down in the air, away from ladders, starts a ground pound
+        if action.just_pressed(&PlatformerAction::Down)
+            && !on_ground
+            && !climber.climbing
+            && climber.intersecting_climbables.is_empty()
+            && !ground_pounder.is_pounding()
+        {
+            *ground_pounder = GroundPounder::Pounding;
+            animation_event.send(AnimationEvent::ground_pounding(ent));
+        }
+
+        // slam straight down until we land, ignoring everything else
+        if ground_pounder.is_pounding() {
+            velocity.linvel = Vec2::new(0., -GROUND_POUND_VELOCITY);
+            continue;
+        }
+
+        // get what the player's standing on
+        let surface = match &ground_detection {
+            GroundDetection::OnGround(ground_ent) => {
+                surfaces_query.get(*ground_ent).copied().unwrap_or_default()
+            }
+            GroundDetection::NotOnGround => Surface::Normal,
+        };
+
+        // if on a platform or conveyor, get its velocity
         // this is the base velocity on top of any user input movement velocity
         let (base_x_vel, base_y_vel) = match &ground_detection {
             GroundDetection::OnGround(ground_ent) => {
-                let mut x_vel = 0.;
-                let mut y_vel = 0.;
+                let mut vel = surface.base_velocity();
                 if let Ok((_, platform_vel)) = platforms_query.get(*ground_ent) {
-                    x_vel = platform_vel.linvel.x;
-                    y_vel = platform_vel.linvel.y;
+                    vel += platform_vel.linvel;
                 }
-                (x_vel, y_vel)
+                (vel.x, vel.y)
             }
             GroundDetection::NotOnGround => (0., 0.),
         };
 
+        // sticky ground slows everything down
+        let (run_velocity, jump_velocity) = match surface {
+            Surface::Sticky => (
+                RUN_VELOCITY * STICKY_SPEED_SCALE,
+                JUMP_VELOCITY * STICKY_SPEED_SCALE,
+            ),
+            _ => (RUN_VELOCITY, JUMP_VELOCITY),
+        };
+
         // handle running
 
         // see if the player just pressed right/left
         let pressed_right = action.pressed(&PlatformerAction::Right);
         let pressed_left = action.pressed(&PlatformerAction::Left);
 
-        // set x velocity
-        if pressed_right && !pressed_left {
-            velocity.linvel.x = base_x_vel + RUN_VELOCITY;
+        // get the x velocity we're running at
+        let target_x_vel = if pressed_right && !pressed_left {
             if !jumper.is_jumping() {
                 animation_event.send(AnimationEvent::running(ent, RunningDirection::Right));
             }
+            base_x_vel + run_velocity
         } else if pressed_left && !pressed_right {
-            velocity.linvel.x = base_x_vel + -RUN_VELOCITY;
             if !jumper.is_jumping() {
                 animation_event.send(AnimationEvent::running(ent, RunningDirection::Left));
             }
+            base_x_vel + -run_velocity
         } else {
-            velocity.linvel.x = base_x_vel;
+            base_x_vel
+        };
+
+        // set x velocity. on ice we keep our momentum & only gradually reach
+        // the running velocity
+        if surface == Surface::Ice {
+            let max_change = ICE_ACCELERATION * time.delta_secs();
+            velocity.linvel.x += (target_x_vel - velocity.linvel.x).clamp(-max_change, max_change);
+        } else {
+            velocity.linvel.x = target_x_vel;
         }
 
         // handle climbing
@@ -167,7 +241,7 @@ pub fn player_movement(
         // the user pressed jump in the air recently
         if on_ground && !jump_buffer_timer.0.is_stopped() {
             jump_buffer_timer.0.pause();
-            velocity.linvel.y = base_y_vel + JUMP_VELOCITY;
+            velocity.linvel.y = base_y_vel + jump_velocity;
             *jumper = Jumper::mk_jumping();
         }
 
@@ -188,7 +262,7 @@ pub fn player_movement(
                         coyote_timer.0.pause();
 
                         // set the y vel
-                        velocity.linvel.y = base_y_vel + JUMP_VELOCITY;
+                        velocity.linvel.y = base_y_vel + jump_velocity;
 
                         // set game state
                         *jumper = Jumper::mk_jumping();
@@ -202,7 +276,7 @@ pub fn player_movement(
                         // see if you have any jumps left, and if so decrement
                         // your remaining jumps
                         if jumping.jumps_left > 0 {
-                            velocity.linvel.y = base_y_vel + JUMP_VELOCITY;
+                            velocity.linvel.y = base_y_vel + jump_velocity;
                             jumping.jumps_left -= 1;
                         } else {
                             // trigger the jump buffer
@@ -221,7 +295,7 @@ pub fn player_movement(
 
         // set movement state
         if !pressed_left
-            && !pressed_left
+            && !pressed_right
             && !just_pressed_jump
             && !climber.climbing
             && !jumper.is_jumping()
@@ -233,30 +307,77 @@ pub fn player_movement(
     }
 }
 
-// ACTIONS
-
-/// configure the keys -> action mapping  for the player
-fn setup_player_actions(mut commands: Commands, mut query: Query<Entity, Added<Player>>) {
-    if query.is_empty() {
-        return;
+/// shake the camera when the player lands after a long fall
+fn shake_on_heavy_landing(
+    mut camera_shake_event: EventWriter<CameraShakeEvent>,
+    mut landing_events: EventReader<LandingEvent>,
+    query: Query<(), With<Player>>,
+) {
+    for landing_event in landing_events.read() {
+        if query.contains(landing_event.ent) && landing_event.strength > HEAVY_LANDING_VELOCITY {
+            camera_shake_event.send(CameraShakeEvent::heavy_landing());
+        }
     }
-    let player_ent = query.single_mut();
-    if let Some(mut ent_cmds) = commands.get_entity(player_ent) {
-        let player_input_map = InputMap::new([
-            (PlatformerAction::Jump, KeyCode::Space),
-            (PlatformerAction::Right, KeyCode::ArrowRight),
-            (PlatformerAction::Left, KeyCode::ArrowLeft),
-            (PlatformerAction::Up, KeyCode::ArrowUp),
-            (PlatformerAction::Down, KeyCode::ArrowDown),
-            (PlatformerAction::Down, KeyCode::ArrowDown),
-            (PlatformerAction::RespawnLevel, KeyCode::KeyR),
-            (PlatformerAction::RespawnWorld, KeyCode::KeyG),
-        ]);
-        ent_cmds.insert(InputManagerBundle::with_map(player_input_map));
+}
+
+/// when a ground pound lands, shake the camera & either bounce off the enemy
+/// it landed on or break the blocks under the player
+#[allow(clippy::type_complexity)]
+fn land_ground_pound(
+    mut landing_events: EventReader<LandingEvent>,
+    mut animation_event: EventWriter<AnimationEvent>,
+    mut camera_shake_event: EventWriter<CameraShakeEvent>,
+    mut break_blocks_event: EventWriter<BreakBlocksEvent>,
+    enemies: Query<(), With<Enemy>>,
+    mut player_query: Query<
+        (
+            &mut GroundPounder,
+            &mut Jumper,
+            &mut Velocity,
+            &Transform,
+            &Collider,
+        ),
+        With<Player>,
+    >,
+) {
+    for landing_event in landing_events.read() {
+        let Ok((mut ground_pounder, mut jumper, mut velocity, transform, collider)) =
+            player_query.get_mut(landing_event.ent)
+        else {
+            continue;
+        };
+        if !ground_pounder.is_pounding() {
+            continue;
+        }
+        *ground_pounder = GroundPounder::NotPounding;
+        camera_shake_event.send(CameraShakeEvent::ground_pound());
+
+        if enemies.contains(landing_event.ground) {
+            velocity.linvel.y = GROUND_POUND_BOUNCE_VELOCITY;
+            *jumper = Jumper::mk_jumping();
+            animation_event.send(AnimationEvent::jumping(landing_event.ent));
+        } else {
+            // everything just under the player's feet
+            let aabb = collider.raw.compute_local_aabb();
+            let position = transform.translation.truncate();
+            break_blocks_event.send(BreakBlocksEvent {
+                area: Rect::new(
+                    position.x + aabb.mins.x,
+                    position.y + aabb.mins.y - GROUND_POUND_DEPTH,
+                    position.x + aabb.maxs.x,
+                    position.y + aabb.mins.y,
+                ),
+                permanent: false,
+            });
+        }
     }
 }
 
-/// configure the keys -> action mapping  for the player
+// ACTIONS
+
+/// configure the keys -> action mapping  for the player. The respawn actions
+/// are for testing levels, so they're only handled in dev builds
+#[cfg(feature = "dev")]
 fn handle_game_actions(
     mut level_respawn_event: EventWriter<RespawnLevelEvent>,
     mut world_respawn_event: EventWriter<RespawnWorldEvent>,
@@ -275,8 +396,8 @@ fn handle_game_actions(
 
 #[derive(Event, PartialEq, Debug, Copy, Clone)]
 pub struct AnimationEvent {
-    ent: Entity,
-    typ: AnimationEventType,
+    pub ent: Entity,
+    pub typ: AnimationEventType,
 }
 impl AnimationEvent {
     fn idling(ent: Entity) -> Self {
@@ -303,6 +424,12 @@ impl AnimationEvent {
             typ: AnimationEventType::Jumping,
         }
     }
+    fn ground_pounding(ent: Entity) -> Self {
+        AnimationEvent {
+            ent,
+            typ: AnimationEventType::GroundPounding,
+        }
+    }
 }
 
 #[derive(PartialEq, Debug, Copy, Clone)]
@@ -311,6 +438,7 @@ pub enum AnimationEventType {
     Running(RunningDirection),
     Climbing(ClimbingDirection),
     Jumping,
+    GroundPounding,
 }
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum RunningDirection {
@@ -323,115 +451,54 @@ pub enum ClimbingDirection {
     Down,
 }
 
-// animating config
-#[derive(Component, PartialEq, Debug)]
-struct AnimationConfig {
-    first_sprite_index: usize,
-    skip_sprite_indexes: Vec<usize>,
-    last_sprite_index: usize,
-    fps: u8,
-    frame_timer: Timer,
-    frame_timer_mode: TimerMode,
-    for_event: AnimationEventType,
-}
-impl AnimationConfig {
-    fn new(
-        first: usize,
-        skip: Vec<usize>,
-        last: usize,
-        fps: u8,
-        timer_mode: TimerMode,
-        for_event: AnimationEventType,
-    ) -> Self {
-        Self {
-            first_sprite_index: first,
-            skip_sprite_indexes: skip,
-            last_sprite_index: last,
-            fps,
-            frame_timer: Self::timer_from_fps(fps, timer_mode),
-            frame_timer_mode: timer_mode,
-            for_event,
-        }
-    }
+/// the player's animation clips, see `assets/player.anim.json`
+const PLAYER_ANIMATIONS: &str = "player.anim.json";
 
-    fn timer_from_fps(fps: u8, timer_mode: TimerMode) -> Timer {
-        Timer::new(Duration::from_secs_f32(1.0 / (fps as f32)), timer_mode)
+/// get the name of the clip to play for the provided movement event
+fn clip_for_movement_event(event_type: &AnimationEventType) -> &'static str {
+    match event_type {
+        AnimationEventType::Idling => "idle",
+        AnimationEventType::Climbing(_) => "climb",
+        AnimationEventType::Jumping => "jump",
+        AnimationEventType::Running(_) => "run",
+        AnimationEventType::GroundPounding => "ground_pound",
     }
 }
 
 /// set the sprite animation for player
 fn recieve_animation_event(
     mut commands: Commands,
+    asset_server: Res<AssetServer>,
     mut animation_events: EventReader<AnimationEvent>,
-    mut animation_config_query: Query<&mut AnimationConfig, With<Player>>,
+    mut animation_query: Query<(Option<&mut SpriteAnimation>, &mut Sprite), With<Player>>,
 ) {
     for animation_event in animation_events.read() {
-        let next_animation = get_anmation_for_movement_event(&animation_event.typ);
-        if let Ok(mut animation) = animation_config_query.get_mut(animation_event.ent) {
-            if animation.for_event != next_animation.for_event {
-                *animation = next_animation;
-            }
-        } else {
-            if let Some(mut ent_cmds) = commands.get_entity(animation_event.ent) {
-                ent_cmds.insert(next_animation);
+        let clip = clip_for_movement_event(&animation_event.typ);
+        let Ok((animation, mut sprite)) = animation_query.get_mut(animation_event.ent) else {
+            continue;
+        };
+        match animation {
+            Some(mut animation) => animation.play(clip),
+            None => {
+                commands
+                    .entity(animation_event.ent)
+                    .insert(SpriteAnimation::new(
+                        asset_server.load(PLAYER_ANIMATIONS),
+                        clip,
+                    ));
             }
         }
-    }
-}
-
-/// for the provided movement state, get the animation config
-fn get_anmation_for_movement_event(event_type: &AnimationEventType) -> AnimationConfig {
-    match event_type {
-        AnimationEventType::Idling | AnimationEventType::Climbing(_) => {
-            AnimationConfig::new(0, vec![1, 2], 3, 3, TimerMode::Repeating, *event_type)
-        }
-        AnimationEventType::Jumping => {
-            AnimationConfig::new(2, vec![], 2, 10, TimerMode::Repeating, *event_type)
-        }
-        AnimationEventType::Running(_) => {
-            AnimationConfig::new(1, vec![2, 3], 4, 15, TimerMode::Repeating, *event_type)
-        }
-    }
-}
 
-/// animate the sprite with the current animation config
-fn animate_sprite(time: Res<Time>, mut query: Query<(&mut AnimationConfig, &mut Sprite)>) {
-    for (mut animation, mut sprite) in &mut query {
-        animation.frame_timer.tick(time.delta());
-        if animation.frame_timer.just_finished() {
-            if let Some(atlas) = &mut sprite.texture_atlas {
-                let mut next_index = atlas.index + 1;
-                while animation.skip_sprite_indexes.contains(&next_index) {
-                    next_index = next_index + 1;
-                }
-                if next_index > animation.last_sprite_index {
-                    atlas.index = animation.first_sprite_index
-                } else {
-                    atlas.index = next_index;
-                    animation.frame_timer =
-                        AnimationConfig::timer_from_fps(animation.fps, animation.frame_timer_mode);
-                };
-            }
+        // flip the sprite based on the players movement direction, including
+        // on the event that starts the first clip
+        match animation_event.typ {
+            AnimationEventType::Running(RunningDirection::Right) => sprite.flip_x = false,
+            AnimationEventType::Running(RunningDirection::Left) => sprite.flip_x = true,
+            _ => (),
         }
     }
 }
 
-/// flip the sprite animation based on the players movement direction
-fn set_sprite_direction(
-    mut query: Query<(&mut Sprite, &AnimationConfig), (With<Player>, Changed<AnimationConfig>)>,
-) {
-    if query.is_empty() {
-        return;
-    }
-
-    let (mut sprite, animation) = query.single_mut();
-    match animation.for_event {
-        AnimationEventType::Running(RunningDirection::Right) => sprite.flip_x = false,
-        AnimationEventType::Running(RunningDirection::Left) => sprite.flip_x = true,
-        _ => (),
-    }
-}
-
 // JUMP BUFFR TIMER
 
 /// store the jump buffer
@@ -461,25 +528,25 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<AnimationEvent>()
-            .register_ldtk_entity::<PlayerBundle>("Player")
+            .register_entity_kind::<PlayerBundle>(EntityKind::Player)
             .add_systems(
-                Update,
-                // player movement systems
+                FixedUpdate,
+                // player movement systems, run on a fixed tick so recorded
+                // inputs replay the same
                 (
-                    setup_player_actions,
-                    handle_game_actions,
-                    player_movement,
+                    land_ground_pound.before(player_movement),
+                    player_movement.run_if(not_transitioning),
                     tick_jump_buffer,
-                ),
+                )
+                    .in_set(GameplaySet::Act),
             )
+            .add_systems(Update, shake_on_heavy_landing)
             .add_systems(
                 Update,
                 // sprite systems
-                (
-                    recieve_animation_event,
-                    set_sprite_direction.after(recieve_animation_event),
-                    animate_sprite,
-                ),
+                recieve_animation_event,
             );
+        #[cfg(feature = "dev")]
+        app.add_systems(FixedUpdate, handle_game_actions.in_set(GameplaySet::Act));
     }
 }
@@ -0,0 +1,396 @@
+//! Which LDtk entities & IntGrid values the game spawns, & what as. Each
+//! project can have a `<project>.registry.json` next to it to use its own
+//! identifiers & numbering, e.g. `TowerOfBabel.registry.json`:
+//!
+//! ```json
+//! {
+//!   "entities": {
+//!     "Player": { "kind": "player" },
+//!     "Skull": { "kind": "mob", "collider": { "cuboid": { "half_size": [6, 6] } } }
+//!   },
+//!   "int_cells": {
+//!     "1": { "kind": "wall" },
+//!     "2": { "kind": "ladder" },
+//!     "3": { "kind": "one_way" },
+//!     "4": { "kind": "wall", "surface": "ice" },
+//!     "5": { "kind": "wall", "surface": { "conveyor": { "speed": -60 } } },
+//!     "6": { "kind": "breakable", "permanent": true }
+//!   }
+//! }
+//! ```
+//!
+//! Projects without one use [`Registry::default`].
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{game_flow::GameFile, surface::Surface};
+
+/// what the game spawns for an LDtk entity
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Player,
+    PlayerRespawn,
+    Mob,
+    Obstacle,
+    Platform,
+    Chest,
+    Pumpkins,
+    CameraZone,
+}
+
+/// what an IntGrid value does
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntCellKind {
+    /// solid from every side. Neighbouring walls are merged into one collider
+    Wall,
+    /// solid from above only, so it can be jumped through from below
+    OneWay,
+    Ladder,
+    Spike,
+    /// a wall that can be broken, see [`crate::breakable`]
+    Breakable,
+}
+
+/// a collider, in pixels
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColliderShape {
+    Cuboid {
+        half_size: [f32; 2],
+        #[serde(default)]
+        offset: [f32; 2],
+    },
+    Ball {
+        radius: f32,
+        #[serde(default)]
+        offset: [f32; 2],
+    },
+    /// several shapes, each with their own offset
+    Compound(Vec<ColliderShape>),
+}
+
+impl ColliderShape {
+    /// the shape & its offset from the entity
+    fn part(&self) -> (Vect, Collider) {
+        match self {
+            Self::Cuboid { half_size, offset } => (
+                Vect::from(*offset),
+                Collider::cuboid(half_size[0], half_size[1]),
+            ),
+            Self::Ball { radius, offset } => (Vect::from(*offset), Collider::ball(*radius)),
+            Self::Compound(shapes) => (
+                Vect::ZERO,
+                Collider::compound(
+                    shapes
+                        .iter()
+                        .map(|shape| {
+                            let (offset, collider) = shape.part();
+                            (offset, 0., collider)
+                        })
+                        .collect(),
+                ),
+            ),
+        }
+    }
+
+    pub fn collider(&self) -> Collider {
+        let (offset, collider) = self.part();
+        if offset == Vect::ZERO {
+            collider
+        } else {
+            Collider::compound(vec![(offset, 0., collider)])
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct EntityMapping {
+    pub kind: EntityKind,
+    /// replaces the kind's usual collider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collider: Option<ColliderShape>,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct IntCellMapping {
+    pub kind: IntCellKind,
+    /// replaces the kind's usual collider. Walls are merged into rectangles,
+    /// so this only applies to ladders & spikes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collider: Option<ColliderShape>,
+    /// what walls & one-way walls are made of
+    #[serde(default, skip_serializing_if = "is_normal")]
+    pub surface: Surface,
+    /// whether breakable blocks are flagged permanent in the save data when
+    /// they're broken, so they stay broken when their level respawns
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub permanent: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn is_normal(surface: &Surface) -> bool {
+    *surface == Surface::Normal
+}
+
+/// Maps a project's entity identifiers & IntGrid values to what the game
+/// spawns for them
+#[derive(Resource, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Registry {
+    #[serde(default)]
+    pub entities: BTreeMap<String, EntityMapping>,
+    #[serde(default)]
+    pub int_cells: BTreeMap<i32, IntCellMapping>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let entities = [
+            ("Player", EntityKind::Player),
+            ("Player_Respawn", EntityKind::PlayerRespawn),
+            ("Mob", EntityKind::Mob),
+            ("Obstacle", EntityKind::Obstacle),
+            ("Platform", EntityKind::Platform),
+            ("Chest", EntityKind::Chest),
+            ("Pumpkins", EntityKind::Pumpkins),
+            ("Camera_Zone", EntityKind::CameraZone),
+        ];
+        let int_cells = [
+            (1, IntCellKind::Wall), // Ground
+            (2, IntCellKind::Wall), // White Box
+            (3, IntCellKind::Wall), // Black Box
+            (4, IntCellKind::Ladder),
+            (5, IntCellKind::Spike),
+        ];
+        Self {
+            entities: entities
+                .into_iter()
+                .map(|(identifier, kind)| {
+                    let mapping = EntityMapping {
+                        kind,
+                        collider: None,
+                    };
+                    (identifier.to_string(), mapping)
+                })
+                .collect(),
+            int_cells: int_cells
+                .into_iter()
+                .map(|(value, kind)| {
+                    let mapping = IntCellMapping {
+                        kind,
+                        collider: None,
+                        surface: Surface::Normal,
+                        permanent: false,
+                    };
+                    (value, mapping)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+}
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "could not read registry {path:?}: {err}"),
+            Self::Json(path, err) => write!(f, "could not parse registry {path:?}: {err}"),
+        }
+    }
+}
+impl std::error::Error for RegistryError {}
+
+impl Registry {
+    /// where a project's registry lives, e.g. `level.registry.json` for
+    /// `level.ldtk`
+    pub fn path_for_project(project: &Path) -> PathBuf {
+        project.with_extension("registry.json")
+    }
+
+    /// load the registry for the project at this filesystem path, or the
+    /// default one if it doesn't have one
+    pub fn load_for_project(project: &Path) -> Result<Self, RegistryError> {
+        let path = Self::path_for_project(project);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let bytes = fs::read(&path).map_err(|err| RegistryError::Io(path.clone(), err))?;
+        serde_json::from_slice(&bytes).map_err(|err| RegistryError::Json(path, err))
+    }
+
+    pub fn entity_kind(&self, identifier: &str) -> Option<EntityKind> {
+        self.entities.get(identifier).map(|mapping| mapping.kind)
+    }
+
+    pub fn int_cell_kind(&self, value: i32) -> Option<IntCellKind> {
+        self.int_cells.get(&value).map(|mapping| mapping.kind)
+    }
+
+    pub fn surface(&self, value: i32) -> Surface {
+        self.int_cells
+            .get(&value)
+            .map(|mapping| mapping.surface)
+            .unwrap_or_default()
+    }
+
+    pub fn permanent(&self, value: i32) -> bool {
+        self.int_cells
+            .get(&value)
+            .is_some_and(|mapping| mapping.permanent)
+    }
+
+    /// the identifiers of every entity of this kind
+    pub fn identifiers(&self, kind: EntityKind) -> impl Iterator<Item = &str> {
+        self.entities
+            .iter()
+            .filter(move |(_, mapping)| mapping.kind == kind)
+            .map(|(identifier, _)| identifier.as_str())
+    }
+
+    /// the IntGrid values of this kind
+    pub fn int_cell_values(&self, kind: IntCellKind) -> impl Iterator<Item = i32> + '_ {
+        self.int_cells
+            .iter()
+            .filter(move |(_, mapping)| mapping.kind == kind)
+            .map(|(value, _)| *value)
+    }
+}
+
+/// the bundles plugins want spawned for each kind, registered with LDtk once
+/// the registry's loaded
+#[allow(clippy::type_complexity)]
+#[derive(Resource, Default)]
+struct KindRegistrations {
+    entities: Vec<(EntityKind, fn(&mut App, &str))>,
+    int_cells: Vec<(IntCellKind, fn(&mut App, i32))>,
+}
+
+pub trait RegistryAppExt {
+    /// spawn `B` for every LDtk entity the registry maps to `kind`
+    fn register_entity_kind<B: LdtkEntity + Bundle>(&mut self, kind: EntityKind) -> &mut Self;
+    /// insert `B` for every IntGrid value the registry maps to `kind`
+    fn register_int_cell_kind<B: LdtkIntCell + Bundle>(&mut self, kind: IntCellKind) -> &mut Self;
+}
+
+impl RegistryAppExt for App {
+    fn register_entity_kind<B: LdtkEntity + Bundle>(&mut self, kind: EntityKind) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<KindRegistrations>()
+            .entities
+            .push((kind, |app, identifier| {
+                app.register_ldtk_entity::<B>(identifier);
+            }));
+        self
+    }
+
+    fn register_int_cell_kind<B: LdtkIntCell + Bundle>(&mut self, kind: IntCellKind) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<KindRegistrations>()
+            .int_cells
+            .push((kind, |app, value| {
+                app.register_ldtk_int_cell::<B>(value);
+            }));
+        self
+    }
+}
+
+/// swap in the collider from the registry for newly spawned entities & int
+/// cells that have one. bevy_ecs_ldtk names entities after their identifier
+#[allow(clippy::type_complexity)]
+fn apply_registry_colliders(
+    registry: Res<Registry>,
+    mut entities: Query<(&Name, &mut Collider), (Added<Collider>, With<EntityIid>)>,
+    mut int_cells: Query<(&IntGridCell, &mut Collider), (Added<Collider>, Without<EntityIid>)>,
+) {
+    for (name, mut collider) in &mut entities {
+        if let Some(shape) = registry
+            .entities
+            .get(name.as_str())
+            .and_then(|mapping| mapping.collider.as_ref())
+        {
+            *collider = shape.collider();
+        }
+    }
+    for (int_grid_cell, mut collider) in &mut int_cells {
+        if let Some(shape) = registry
+            .int_cells
+            .get(&int_grid_cell.value)
+            .and_then(|mapping| mapping.collider.as_ref())
+        {
+            *collider = shape.collider();
+        }
+    }
+}
+
+/// Loads the game file's registry & registers every plugin's bundles with
+/// LDtk
+pub struct RegistryPlugin;
+
+impl Plugin for RegistryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KindRegistrations>().add_systems(
+            PreUpdate,
+            apply_registry_colliders.after(bevy_ecs_ldtk::systems::process_ldtk_levels),
+        );
+    }
+
+    // the game file isn't known until every plugin's been added. A registry
+    // that's already been inserted, e.g. by a test, is used instead
+    fn finish(&self, app: &mut App) {
+        let registry = match app.world_mut().remove_resource::<Registry>() {
+            Some(registry) => registry,
+            None => Self::load_registry(app),
+        };
+
+        let registrations = app
+            .world_mut()
+            .remove_resource::<KindRegistrations>()
+            .unwrap_or_default();
+        for (kind, register) in registrations.entities {
+            for identifier in registry.identifiers(kind) {
+                register(app, identifier);
+            }
+        }
+        for (kind, register) in registrations.int_cells {
+            for value in registry.int_cell_values(kind) {
+                register(app, value);
+            }
+        }
+        app.insert_resource(registry);
+    }
+}
+
+impl RegistryPlugin {
+    /// the game file's registry, or the default one
+    fn load_registry(app: &App) -> Registry {
+        match app.world().get_resource::<GameFile>() {
+            Some(game_file) => {
+                let project = FileAssetReader::get_base_path()
+                    .join("assets")
+                    .join(&game_file.path);
+                Registry::load_for_project(&project).unwrap_or_else(|err| {
+                    error!("{err}, using the default registry");
+                    Registry::default()
+                })
+            }
+            None => Registry::default(),
+        }
+    }
+}
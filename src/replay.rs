@@ -0,0 +1,225 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actions::PlatformerAction, controls::Bindings, player::Player};
+
+/// Whether we're recording the player's inputs, replaying them or neither.
+/// Set from the command line with `--record <file>` or `--replay <file>`.
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+pub enum ReplayMode {
+    #[default]
+    Off,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// The actions the player was pressing on every fixed tick, starting from the
+/// first tick the player existed. Consecutive ticks with the same actions are
+/// stored as one run, so holding a direction doesn't bloat the file.
+#[derive(Resource, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct InputRecording {
+    /// the fixed timestep the recording was made with, replays must use the
+    /// same one to play back the same
+    pub timestep_secs: f64,
+    pub runs: Vec<InputRun>,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct InputRun {
+    pub pressed: Vec<PlatformerAction>,
+    pub ticks: u32,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+}
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "could not read replay {path:?}: {err}"),
+            Self::Json(path, err) => write!(f, "could not parse replay {path:?}: {err}"),
+        }
+    }
+}
+impl std::error::Error for ReplayError {}
+
+impl InputRecording {
+    fn push(&mut self, mut pressed: Vec<PlatformerAction>) {
+        // sort so the same actions always compare equal
+        pressed.sort_by_key(|action| PlatformerAction::ALL.iter().position(|a| a == action));
+        match self.runs.last_mut() {
+            Some(run) if run.pressed == pressed => run.ticks += 1,
+            _ => self.runs.push(InputRun { pressed, ticks: 1 }),
+        }
+    }
+
+    /// the actions pressed on the provided tick, None once the recording ends
+    fn pressed_at(&self, mut tick: u32) -> Option<&[PlatformerAction]> {
+        for run in &self.runs {
+            if tick < run.ticks {
+                return Some(&run.pressed);
+            }
+            tick -= run.ticks;
+        }
+        None
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let bytes = fs::read(path).map_err(|err| ReplayError::Io(path.to_path_buf(), err))?;
+        serde_json::from_slice(&bytes).map_err(|err| ReplayError::Json(path.to_path_buf(), err))
+    }
+
+    pub fn save(&self, path: &Path) {
+        let json = serde_json::to_string(self).expect("recording should serialize");
+        if let Err(err) = fs::write(path, json) {
+            error!("could not save recording to {path:?}: {err}");
+        }
+    }
+}
+
+/// quit once the replay ends, for replaying without a window
+#[derive(Resource, Clone, Copy, PartialEq, Debug, Default)]
+pub struct ExitAfterReplay;
+
+/// how many fixed ticks have been replayed
+#[derive(Resource, Clone, Copy, PartialEq, Debug, Default)]
+struct ReplayTick(u32);
+
+/// start recording, or load the replay. A replay that can't be loaded is
+/// skipped, & the game starts without it
+fn setup_replay(
+    mut commands: Commands,
+    mut replay_mode: ResMut<ReplayMode>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    opt_exit_after_replay: Option<Res<ExitAfterReplay>>,
+    mut exit_events: EventWriter<AppExit>,
+) {
+    match replay_mode.clone() {
+        ReplayMode::Off => (),
+        ReplayMode::Record(_) => {
+            commands.insert_resource(InputRecording {
+                timestep_secs: fixed_time.timestep().as_secs_f64(),
+                runs: Vec::new(),
+            });
+        }
+        ReplayMode::Replay(path) => match InputRecording::load(&path) {
+            Ok(recording) => {
+                fixed_time.set_timestep(Duration::from_secs_f64(recording.timestep_secs));
+                commands.insert_resource(recording);
+            }
+            Err(err) => {
+                error!("{err}, starting without it");
+                *replay_mode = ReplayMode::Off;
+                // there's nothing to wait for
+                if opt_exit_after_replay.is_some() {
+                    exit_events.send(AppExit::error());
+                }
+            }
+        },
+    }
+}
+
+/// record what the player is pressing this tick
+fn record_inputs(
+    mut recording: ResMut<InputRecording>,
+    players: Query<&ActionState<PlatformerAction>, With<Player>>,
+) {
+    if let Ok(action_state) = players.get_single() {
+        recording.push(action_state.get_pressed());
+    }
+}
+
+/// save the recording when the game closes
+fn save_recording(
+    mut exit_events: EventReader<AppExit>,
+    replay_mode: Res<ReplayMode>,
+    recording: Res<InputRecording>,
+) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+    if let ReplayMode::Record(path) = replay_mode.as_ref() {
+        recording.save(path);
+        info!("saved recording to {path:?}");
+    }
+}
+
+/// drive the player with the recorded actions. The player's input map is
+/// emptied while replaying, so the real keyboard & gamepad are ignored
+fn replay_inputs(
+    recording: Res<InputRecording>,
+    bindings: Res<Bindings>,
+    opt_exit_after_replay: Option<Res<ExitAfterReplay>>,
+    mut exit_events: EventWriter<AppExit>,
+    mut tick: ResMut<ReplayTick>,
+    mut players: Query<
+        (
+            &mut ActionState<PlatformerAction>,
+            &mut InputMap<PlatformerAction>,
+        ),
+        With<Player>,
+    >,
+) {
+    let Ok((mut action_state, mut input_map)) = players.get_single_mut() else {
+        return;
+    };
+
+    let Some(pressed) = recording.pressed_at(tick.0) else {
+        // hand control back to the player once the recording ends
+        if input_map.is_empty() {
+            info!("replay finished after {} ticks", tick.0);
+            *input_map = bindings.0.clone();
+            if opt_exit_after_replay.is_some() {
+                exit_events.send(AppExit::Success);
+            }
+        }
+        return;
+    };
+    if !input_map.is_empty() {
+        *input_map = InputMap::default();
+    }
+    for &action in PlatformerAction::ALL {
+        if pressed.contains(&action) {
+            action_state.press(&action);
+        } else {
+            action_state.release(&action);
+        }
+    }
+    tick.0 += 1;
+}
+
+fn recording(replay_mode: Res<ReplayMode>) -> bool {
+    matches!(*replay_mode, ReplayMode::Record(_))
+}
+
+fn replaying(replay_mode: Res<ReplayMode>) -> bool {
+    matches!(*replay_mode, ReplayMode::Replay(_))
+}
+
+/// records the player's inputs to a file, or replays them from one
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayMode>()
+            .init_resource::<ReplayTick>()
+            .add_systems(Startup, setup_replay)
+            .add_systems(
+                FixedPreUpdate,
+                (
+                    record_inputs.run_if(recording),
+                    replay_inputs.run_if(replaying),
+                ),
+            )
+            .add_systems(Last, save_recording.run_if(recording));
+    }
+}
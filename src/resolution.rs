@@ -0,0 +1,160 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{RenderTarget, Viewport},
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+    },
+    window::PrimaryWindow,
+};
+
+use crate::camera::{CameraConfig, CameraController};
+
+/// Render layer for things drawn directly to the window, rather than through
+/// the game camera
+const WINDOW_LAYER: RenderLayers = RenderLayers::layer(1);
+
+/// Camera that draws directly to the window. It clears the window to black so
+/// any space the game view doesn't cover is letterboxed. When rendering pixel
+/// perfect, it also draws the canvas the game camera renders to.
+#[derive(Component)]
+struct WindowCamera;
+
+/// Sprite showing the low resolution image the game camera renders to, when
+/// rendering pixel perfect
+#[derive(Component)]
+struct Canvas;
+
+/// once the game camera is spawned, spawn the camera for the window & if
+/// we're rendering pixel perfect, point the game camera at a low resolution
+/// canvas
+fn setup_render_target(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    camera_config: Res<CameraConfig>,
+    mut game_camera_query: Query<(Entity, &mut Camera), Added<CameraController>>,
+) {
+    for (camera_ent, mut camera) in &mut game_camera_query {
+        if camera_config.pixel_perfect {
+            let resolution = camera_config.resolution();
+            let canvas_size = Extent3d {
+                width: resolution.x,
+                height: resolution.y,
+                ..default()
+            };
+            let mut canvas = Image {
+                texture_descriptor: TextureDescriptor {
+                    label: None,
+                    size: canvas_size,
+                    dimension: TextureDimension::D2,
+                    format: TextureFormat::Bgra8UnormSrgb,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    usage: TextureUsages::TEXTURE_BINDING
+                        | TextureUsages::COPY_DST
+                        | TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                },
+                ..default()
+            };
+            canvas.resize(canvas_size);
+            let canvas_handle = images.add(canvas);
+
+            // Render the game to the canvas, before the window camera draws it
+            camera.order = -1;
+            camera.target = RenderTarget::Image(canvas_handle.clone());
+            commands.entity(camera_ent).insert(Msaa::Off);
+
+            commands.spawn((Sprite::from_image(canvas_handle), Canvas, WINDOW_LAYER));
+            commands.spawn((
+                Camera2d,
+                Camera {
+                    clear_color: ClearColorConfig::Custom(Color::BLACK),
+                    ..default()
+                },
+                Msaa::Off,
+                WindowCamera,
+                WINDOW_LAYER,
+            ));
+        } else {
+            // Clear the window before the game camera draws to its viewport
+            commands.spawn((
+                Camera2d,
+                Camera {
+                    order: -1,
+                    clear_color: ClearColorConfig::Custom(Color::BLACK),
+                    ..default()
+                },
+                WindowCamera,
+                WINDOW_LAYER,
+            ));
+        }
+    }
+}
+
+/// fit the game view inside the window, keeping the configured aspect ratio
+#[allow(clippy::type_complexity)]
+fn fit_view_to_window(
+    camera_config: Res<CameraConfig>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut game_camera_query: Query<&mut Camera, With<CameraController>>,
+    mut window_camera_query: Query<
+        &mut OrthographicProjection,
+        (With<WindowCamera>, Without<CameraController>),
+    >,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let window_size = window.physical_size().as_vec2();
+    if window_size.min_element() <= 0. {
+        return;
+    }
+
+    if camera_config.pixel_perfect {
+        // Scale the canvas up by the largest whole number that fits
+        let resolution = camera_config.resolution().as_vec2();
+        let scale = (window_size / resolution).min_element().floor().max(1.);
+        for mut projection in &mut window_camera_query {
+            projection.scale = window.scale_factor() / scale;
+        }
+    } else {
+        // Letterbox the game camera's viewport
+        let aspect_ratio = camera_config.aspect_ratio();
+        let viewport_size = if window_size.x / window_size.y > aspect_ratio {
+            Vec2::new(window_size.y * aspect_ratio, window_size.y)
+        } else {
+            Vec2::new(window_size.x, window_size.x / aspect_ratio)
+        }
+        .floor()
+        .as_uvec2();
+        let viewport_position = window_size.as_uvec2().saturating_sub(viewport_size) / 2;
+
+        for mut camera in &mut game_camera_query {
+            // Only touch the camera when the viewport changes, so we don't
+            // trigger change detection every frame
+            let unchanged = camera.viewport.as_ref().is_some_and(|viewport| {
+                viewport.physical_position == viewport_position
+                    && viewport.physical_size == viewport_size
+            });
+            if !unchanged {
+                camera.viewport = Some(Viewport {
+                    physical_position: viewport_position,
+                    physical_size: viewport_size,
+                    ..default()
+                });
+            }
+        }
+    }
+}
+
+/// handles the game's resolution, letterboxing & pixel perfect rendering
+pub struct ResolutionPlugin;
+
+impl Plugin for ResolutionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (setup_render_target, fit_view_to_window).chain());
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{camera::CameraController, player::Player};
+
+// room transition constants
+
+/// How long the camera takes to pan to the new level
+const ROOM_TRANSITION_SECS: f32 = 0.4;
+/// How far the player is pushed into the new level when a transition starts,
+/// so they don't immediately cross back into the level they came from
+const ROOM_TRANSITION_NUDGE: f32 = 8.;
+
+/// sent when the player crosses into a different level
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RoomTransitionEvent {
+    /// the world-space bounds of the level the player entered
+    pub level_bounds: Rect,
+}
+
+/// tracks if we're in the middle of moving between levels
+#[derive(Clone, Debug, Default, Resource)]
+pub enum RoomTransition {
+    #[default]
+    Idle,
+    Transitioning(Transitioning),
+}
+impl RoomTransition {
+    pub fn is_transitioning(&self) -> bool {
+        match self {
+            Self::Idle => false,
+            Self::Transitioning(_) => true,
+        }
+    }
+}
+
+/// run condition for systems that should pause while moving between levels
+pub fn not_transitioning(room_transition: Res<RoomTransition>) -> bool {
+    !room_transition.is_transitioning()
+}
+
+#[derive(Clone, Debug)]
+pub struct Transitioning {
+    timer: Timer,
+    /// where the camera was when the transition started
+    camera_from: Vec2,
+    /// the players velocity when they were frozen, restored once the
+    /// transition is done
+    player_velocity: Vec2,
+}
+impl Transitioning {
+    /// blend from the camera's starting position to the provided target,
+    /// based on how far along the transition we are
+    pub fn camera_translation(&self, target: Vec2) -> Vec2 {
+        let t = self.timer.fraction();
+        // smoothstep, so the pan eases in & out
+        let eased = t * t * (3. - 2. * t);
+        self.camera_from.lerp(target, eased)
+    }
+}
+
+/// when the player crosses into a new level, freeze them, nudge them through
+/// the doorway and start panning the camera
+fn start_room_transition(
+    mut commands: Commands,
+    mut transition_events: EventReader<RoomTransitionEvent>,
+    mut room_transition: ResMut<RoomTransition>,
+    camera_query: Query<&Transform, (With<CameraController>, Without<Player>)>,
+    mut player_query: Query<(Entity, &mut Transform, &mut Velocity), With<Player>>,
+) {
+    // only the most recent transition matters
+    let Some(transition_event) = transition_events.read().last() else {
+        return;
+    };
+    let Ok(camera_transform) = camera_query.get_single() else {
+        return;
+    };
+
+    // If we're already transitioning, keep the original velocity so we don't
+    // restore the zeroed out one
+    let opt_existing_velocity = match room_transition.as_ref() {
+        RoomTransition::Transitioning(transitioning) => Some(transitioning.player_velocity),
+        RoomTransition::Idle => None,
+    };
+
+    for (player_ent, mut player_transform, mut velocity) in &mut player_query {
+        let player_pos = player_transform.translation.truncate();
+        let bounds = transition_event.level_bounds;
+
+        // The doorway is whichever edge of the new level the player is
+        // closest to. Push the player away from it, into the new level
+        let nudge_direction = [
+            (player_pos.x - bounds.min.x, Vec2::X),
+            (bounds.max.x - player_pos.x, Vec2::NEG_X),
+            (player_pos.y - bounds.min.y, Vec2::Y),
+            (bounds.max.y - player_pos.y, Vec2::NEG_Y),
+        ]
+        .into_iter()
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, direction)| direction)
+        .unwrap_or(Vec2::ZERO);
+        player_transform.translation += (nudge_direction * ROOM_TRANSITION_NUDGE).extend(0.);
+
+        // Freeze the player
+        let player_velocity = opt_existing_velocity.unwrap_or(velocity.linvel);
+        velocity.linvel = Vec2::ZERO;
+        commands.entity(player_ent).insert(RigidBodyDisabled);
+
+        *room_transition = RoomTransition::Transitioning(Transitioning {
+            timer: Timer::new(
+                Duration::from_secs_f32(ROOM_TRANSITION_SECS),
+                TimerMode::Once,
+            ),
+            camera_from: camera_transform.translation.truncate(),
+            player_velocity,
+        });
+    }
+}
+
+/// tick the transition, and once it's done unfreeze the player
+fn tick_room_transition(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut room_transition: ResMut<RoomTransition>,
+    mut player_query: Query<(Entity, &mut Velocity), With<Player>>,
+) {
+    if let RoomTransition::Transitioning(transitioning) = room_transition.as_mut() {
+        transitioning.timer.tick(time.delta());
+        if transitioning.timer.finished() {
+            for (player_ent, mut velocity) in &mut player_query {
+                velocity.linvel = transitioning.player_velocity;
+                commands.entity(player_ent).remove::<RigidBodyDisabled>();
+            }
+            *room_transition = RoomTransition::Idle;
+        }
+    }
+}
+
+/// handles freezing the player & panning the camera between levels
+pub struct RoomTransitionPlugin;
+
+impl Plugin for RoomTransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RoomTransitionEvent>()
+            .init_resource::<RoomTransition>()
+            .add_systems(
+                Update,
+                (start_room_transition, tick_room_transition).chain(),
+            );
+    }
+}
@@ -0,0 +1,176 @@
+//! The player's save data. It records every broken block & whether it's
+//! flagged permanent. It's loaded into [`BrokenBlocks`] at startup & saved
+//! again whenever they change, next to the user's bindings.
+//!
+//! Starting the game is like respawning the world, so only the blocks flagged
+//! permanent in the save data stay broken.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::breakable::{BlockLocation, BrokenBlocks};
+
+/// where the save data is saved, inside the config directory
+const SAVE_FILE: &str = "bean-platformer/save.json";
+
+/// this file's path inside the user's config directory, if they have one
+pub fn config_path(file: &str) -> Option<PathBuf> {
+    let config_dir = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    config_dir.map(|config_dir| config_dir.join(file))
+}
+
+/// a broken block, as it's saved
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SavedBlock {
+    pub level_iid: String,
+    pub layer: String,
+    pub grid_coords: [i32; 2],
+    /// whether it stays broken when its level respawns
+    pub permanent: bool,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct SaveData {
+    pub broken_blocks: Vec<SavedBlock>,
+}
+
+impl SaveData {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    /// the broken blocks a new game starts with: the permanent ones
+    pub fn broken_blocks(&self) -> BrokenBlocks {
+        BrokenBlocks(
+            self.broken_blocks
+                .iter()
+                .filter(|block| block.permanent)
+                .map(|block| {
+                    let [x, y] = block.grid_coords;
+                    let location = BlockLocation {
+                        level_iid: block.level_iid.clone(),
+                        layer: block.layer.clone(),
+                        grid_coords: GridCoords::new(x, y),
+                    };
+                    (location, true)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl From<&BrokenBlocks> for SaveData {
+    fn from(broken_blocks: &BrokenBlocks) -> Self {
+        let mut broken_blocks: Vec<_> = broken_blocks
+            .0
+            .iter()
+            .map(|(location, &permanent)| SavedBlock {
+                level_iid: location.level_iid.clone(),
+                layer: location.layer.clone(),
+                grid_coords: [location.grid_coords.x, location.grid_coords.y],
+                permanent,
+            })
+            .collect();
+        // keep the file stable between saves
+        broken_blocks.sort_by(|a, b| {
+            (&a.level_iid, &a.layer, a.grid_coords).cmp(&(&b.level_iid, &b.layer, b.grid_coords))
+        });
+        Self { broken_blocks }
+    }
+}
+
+/// load the user's save data, starting a new one if they don't have any or it
+/// can't be read
+fn load_save_data(mut commands: Commands) {
+    let Some(path) = config_path(SAVE_FILE).filter(|path| path.exists()) else {
+        return;
+    };
+    match SaveData::read(&path) {
+        Ok(save_data) => commands.insert_resource(save_data.broken_blocks()),
+        Err(err) => warn!("could not load save data {path:?}, starting a new game: {err}"),
+    }
+}
+
+fn save_save_data(broken_blocks: Res<BrokenBlocks>) {
+    if !broken_blocks.is_changed() || broken_blocks.is_added() {
+        return;
+    }
+    let Some(path) = config_path(SAVE_FILE) else {
+        warn!("could not find a config directory to save to");
+        return;
+    };
+    if let Err(err) = SaveData::from(broken_blocks.as_ref()).write(&path) {
+        warn!("could not save to {path:?}: {err}");
+    }
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_save_data)
+            .add_systems(Last, save_save_data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(x: i32, permanent: bool) -> SavedBlock {
+        SavedBlock {
+            level_iid: "level".to_string(),
+            layer: "Collisions".to_string(),
+            grid_coords: [x, 2],
+            permanent,
+        }
+    }
+
+    #[test]
+    fn save_data_round_trips_through_its_file() {
+        let path = env::temp_dir()
+            .join(format!("bean-platformer-{}", std::process::id()))
+            .join("save.json");
+        let save_data = SaveData {
+            broken_blocks: vec![block(1, false), block(2, true)],
+        };
+        save_data.write(&path).unwrap();
+        let read = SaveData::read(&path);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(read.unwrap(), save_data);
+    }
+
+    #[test]
+    fn only_permanent_blocks_stay_broken_in_a_new_game() {
+        let save_data = SaveData {
+            broken_blocks: vec![block(1, false), block(2, true)],
+        };
+        let broken_blocks = save_data.broken_blocks();
+        assert_eq!(broken_blocks.0.len(), 1);
+        assert_eq!(
+            SaveData::from(&broken_blocks).broken_blocks,
+            [block(2, true)]
+        );
+    }
+}
@@ -2,7 +2,13 @@ use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::CollisionEvent;
 
-use crate::{colliders::SensorBundle, game_flow::RespawnLevelEvent};
+use crate::{
+    audio::{GameSound, PlaySoundEvent},
+    camera_shake::CameraShakeEvent,
+    colliders::SensorBundle,
+    game_flow::RespawnLevelEvent,
+    registry::{IntCellKind, RegistryAppExt},
+};
 
 /// a spike tag
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
@@ -12,8 +18,11 @@ pub struct Spike;
 #[derive(Clone, Default, Bundle, LdtkIntCell)]
 pub struct SpikeBundle {
     pub spike: Spike,
-    #[from_int_grid_cell]
+    #[with(SensorBundle::spike)]
     pub sensor_bundle: SensorBundle,
+    // the registry looks colliders up by value
+    #[from_int_grid_cell]
+    pub int_grid_cell: IntGridCell,
 }
 
 /// put this on entites that you want to watch for spike collisions
@@ -21,21 +30,27 @@ pub struct SpikeBundle {
 pub struct SpikeDetection;
 
 /// check if there is a collision between a SpikeDetection and a Spike
-/// if so, respawn the level
+/// if so, shake the camera, play the death sound & respawn the level
 pub fn detect_spike(
     to_detect: Query<Entity, With<SpikeDetection>>,
     spikes: Query<Entity, With<Spike>>,
     mut collisions: EventReader<CollisionEvent>,
     mut level_respawn_event: EventWriter<RespawnLevelEvent>,
+    mut camera_shake_event: EventWriter<CameraShakeEvent>,
+    mut sound_event: EventWriter<PlaySoundEvent>,
 ) {
     for collision in collisions.read() {
         match collision {
             CollisionEvent::Started(collider_a, collider_b, _) => {
                 if let (Ok(_), Ok(_)) = (to_detect.get(*collider_a), spikes.get(*collider_b)) {
                     level_respawn_event.send(RespawnLevelEvent::RespawnLevelEvent);
+                    camera_shake_event.send(CameraShakeEvent::damage());
+                    sound_event.send(PlaySoundEvent(GameSound::SpikeDeath));
                 }
                 if let (Ok(_), Ok(_)) = (to_detect.get(*collider_b), spikes.get(*collider_a)) {
                     level_respawn_event.send(RespawnLevelEvent::RespawnLevelEvent);
+                    camera_shake_event.send(CameraShakeEvent::damage());
+                    sound_event.send(PlaySoundEvent(GameSound::SpikeDeath));
                 };
             }
             CollisionEvent::Stopped(_, _, _) => {}
@@ -47,7 +62,7 @@ pub struct SpikePlugin;
 
 impl Plugin for SpikePlugin {
     fn build(&self, app: &mut App) {
-        app.register_ldtk_int_cell::<SpikeBundle>(5)
+        app.register_int_cell_kind::<SpikeBundle>(IntCellKind::Spike)
             .add_systems(Update, detect_spike);
     }
 }
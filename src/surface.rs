@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// what a wall is made of, set per IntGrid value in the
+/// [`crate::registry::Registry`]. Put on the merged wall colliders, so
+/// [`crate::ground_detection::GroundDetection`] tells you what the player's
+/// standing on
+#[derive(Copy, Clone, PartialEq, Debug, Default, Component, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Surface {
+    #[default]
+    Normal,
+    /// slippery, the player speeds up & slows down gradually instead of
+    /// instantly
+    Ice,
+    /// bounces things that land on it back up
+    Bouncy,
+    /// slows the player's running & jumping
+    Sticky,
+    /// carries things standing on it along, in pixels per second. Positive
+    /// speeds go right. Needs [`ActiveHooks::MODIFY_SOLVER_CONTACTS`] on its
+    /// collider for [`crate::colliders::ContactHooks`] to move things
+    Conveyor { speed: f32 },
+}
+
+impl Surface {
+    pub fn friction(&self) -> Friction {
+        match self {
+            Surface::Ice => Friction::new(0.0),
+            _ => Friction::new(1.0),
+        }
+    }
+
+    pub fn restitution(&self) -> Restitution {
+        match self {
+            Surface::Bouncy => Restitution {
+                coefficient: 0.9,
+                combine_rule: CoefficientCombineRule::Max,
+            },
+            _ => Restitution::default(),
+        }
+    }
+
+    /// the hooks this surface needs on its collider
+    pub fn active_hooks(&self) -> ActiveHooks {
+        match self {
+            Surface::Conveyor { .. } => ActiveHooks::MODIFY_SOLVER_CONTACTS,
+            _ => ActiveHooks::empty(),
+        }
+    }
+
+    /// the velocity things standing on it are carried along at
+    pub fn base_velocity(&self) -> Vec2 {
+        match self {
+            Surface::Conveyor { speed } => Vec2::new(*speed, 0.),
+            _ => Vec2::ZERO,
+        }
+    }
+}
@@ -0,0 +1,577 @@
+//! Runs the game headless for tests: no window, no rendering & no audio, with
+//! the player driven by scripted actions instead of a keyboard.
+//!
+//! ```ignore
+//! let mut game = TestGame::new("tests/fixture.ldtk");
+//! game.hold(PlatformerAction::Right);
+//! game.tick(30);
+//! assert!(game.player_position().x > start.x);
+//! ```
+
+use std::{
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    asset::AssetPlugin,
+    ecs::event::EventCursor,
+    input::InputPlugin,
+    prelude::*,
+    render::{render_resource::Shader, sync_world::SyncWorldPlugin},
+    scene::ScenePlugin,
+    state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+    utils::HashSet,
+};
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    actions::PlatformerAction,
+    audio::{GameSound, RequestedSounds, SoundConfig, SoundConfigHandle},
+    camera_shake::CameraShake,
+    controls::ControlsPlugin,
+    fixed_step::TransformInterpolation,
+    game_flow::GameFile,
+    ground_detection::{GroundDetection, LandingEvent},
+    ground_pound::GroundPounder,
+    jumping::Jumper,
+    player::Player,
+    registry::{IntCellKind, Registry},
+    replay::ReplayPlugin,
+    resolution::ResolutionPlugin,
+    save::SavePlugin,
+    walls::WallCollider,
+    FrijoleroPlugins,
+};
+
+/// how long to wait for the project to load before giving up
+const LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// the actions the test is holding down
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+struct ScriptedActions(HashSet<PlatformerAction>);
+
+/// give the player an action state with no input map, so only the script
+/// drives it
+fn add_scripted_action_state(mut commands: Commands, players: Query<Entity, Added<Player>>) {
+    for player_ent in &players {
+        commands
+            .entity(player_ent)
+            .insert(ActionState::<PlatformerAction>::default());
+    }
+}
+
+/// press the actions the script is holding, & release the rest
+fn apply_scripted_actions(
+    scripted_actions: Res<ScriptedActions>,
+    mut players: Query<&mut ActionState<PlatformerAction>, With<Player>>,
+) {
+    for mut action_state in &mut players {
+        for &action in PlatformerAction::ALL {
+            if scripted_actions.0.contains(&action) {
+                action_state.press(&action);
+            } else {
+                action_state.release(&action);
+            }
+        }
+    }
+}
+
+/// The game, running headless. Every call to [`TestGame::tick`] advances the
+/// simulation by exactly one fixed timestep per tick, no matter how long it
+/// takes to run.
+pub struct TestGame {
+    pub app: App,
+    /// the landings [`TestGame::landings`] has already returned
+    landings: EventCursor<LandingEvent>,
+}
+
+impl TestGame {
+    /// start the game on the provided project, relative to the assets folder,
+    /// & wait for the player to spawn
+    pub fn new(ldtk_path: impl AsRef<Path>) -> Self {
+        Self::start(ldtk_path.as_ref(), None)
+    }
+
+    /// start the game on the provided project with this registry, instead of
+    /// the project's own
+    pub fn with_registry(ldtk_path: impl AsRef<Path>, registry: Registry) -> Self {
+        Self::start(ldtk_path.as_ref(), Some(registry))
+    }
+
+    fn start(ldtk_path: &Path, registry: Option<Registry>) -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            HierarchyPlugin,
+            InputPlugin,
+            StatesPlugin,
+            AssetPlugin {
+                file_path: concat!(env!("CARGO_MANIFEST_DIR"), "/assets").to_string(),
+                ..default()
+            },
+            ImagePlugin::default_nearest(),
+            ScenePlugin,
+            // despawning anything drawn needs this, even without a renderer
+            SyncWorldPlugin,
+        ))
+        .init_asset::<TextureAtlasLayout>()
+        .init_asset::<Shader>()
+        .init_resource::<ClearColor>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+            Time::<Fixed>::default().timestep(),
+        ))
+        // the script drives the player instead of the keyboard, there's no
+        // window to fit the view to & the user's save data is left alone
+        .add_plugins(
+            FrijoleroPlugins
+                .build()
+                .disable::<ControlsPlugin>()
+                .disable::<ReplayPlugin>()
+                .disable::<ResolutionPlugin>()
+                .disable::<SavePlugin>(),
+        )
+        .insert_resource(GameFile {
+            path: ldtk_path.to_path_buf(),
+        })
+        .insert_resource(LevelSelection::Uid(0))
+        .insert_resource(LdtkSettings {
+            level_spawn_behavior: LevelSpawnBehavior::UseWorldTranslation {
+                load_level_neighbors: true,
+            },
+            ..default()
+        })
+        .init_resource::<ScriptedActions>()
+        .add_systems(Update, add_scripted_action_state)
+        .add_systems(FixedPreUpdate, apply_scripted_actions);
+
+        if let Some(registry) = registry {
+            app.insert_resource(registry);
+        }
+
+        // App::run would do this, but we update the app ourselves
+        app.finish();
+        app.cleanup();
+
+        let mut game = TestGame {
+            app,
+            landings: default(),
+        };
+        game.wait_for_player();
+        game
+    }
+
+    /// update until the project has loaded & the player has spawned
+    fn wait_for_player(&mut self) {
+        let start = Instant::now();
+        while self.player().is_none() {
+            assert!(
+                start.elapsed() < LOAD_TIMEOUT,
+                "the player should spawn within {LOAD_TIMEOUT:?}"
+            );
+            // assets load on other threads, so don't advance the game while
+            // we wait for them
+            self.app
+                .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+            self.app.update();
+            thread::sleep(Duration::from_millis(1));
+        }
+        self.app.insert_resource(TimeUpdateStrategy::ManualDuration(
+            self.app.world().resource::<Time<Fixed>>().timestep(),
+        ));
+        // let the player's components settle before the test starts
+        self.tick(1);
+    }
+
+    /// advance the game by the provided number of fixed ticks
+    pub fn tick(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    /// start holding the action down, until it's released
+    pub fn hold(&mut self, action: PlatformerAction) {
+        self.app
+            .world_mut()
+            .resource_mut::<ScriptedActions>()
+            .0
+            .insert(action);
+    }
+
+    pub fn release(&mut self, action: PlatformerAction) {
+        self.app
+            .world_mut()
+            .resource_mut::<ScriptedActions>()
+            .0
+            .remove(&action);
+    }
+
+    /// press the action for a single tick
+    pub fn tap(&mut self, action: PlatformerAction) {
+        self.hold(action);
+        self.tick(1);
+        self.release(action);
+    }
+
+    fn player(&mut self) -> Option<Entity> {
+        self.app
+            .world_mut()
+            .query_filtered::<Entity, With<Player>>()
+            .get_single(self.app.world())
+            .ok()
+    }
+
+    fn player_component<T: Component + Clone>(&mut self) -> T {
+        let player = self.player().expect("the player should exist");
+        self.app
+            .world()
+            .get::<T>(player)
+            .expect("the player should have the component")
+            .clone()
+    }
+
+    /// where physics has the player, ignoring interpolation
+    pub fn player_position(&mut self) -> Vec2 {
+        self.player_component::<TransformInterpolation>()
+            .current
+            .unwrap_or_else(|| self.player_component::<Transform>().translation.truncate())
+    }
+
+    pub fn player_velocity(&mut self) -> Vec2 {
+        self.player_component::<Velocity>().linvel
+    }
+
+    /// move the player somewhere else, moving at this velocity
+    pub fn move_player(&mut self, position: Vec2, linvel: Vec2) {
+        let player = self.player().expect("the player should exist");
+        let mut entity = self.app.world_mut().entity_mut(player);
+        let mut transform = entity.get_mut::<Transform>().unwrap();
+        transform.translation = position.extend(transform.translation.z);
+        entity.get_mut::<Velocity>().unwrap().linvel = linvel;
+    }
+
+    pub fn ground_detection(&mut self) -> GroundDetection {
+        self.player_component::<GroundDetection>()
+    }
+
+    pub fn jumper(&mut self) -> Jumper {
+        self.player_component::<Jumper>()
+    }
+
+    pub fn ground_pounder(&mut self) -> GroundPounder {
+        self.player_component::<GroundPounder>()
+    }
+
+    /// everything that's landed since this was last called
+    pub fn landings(&mut self) -> Vec<LandingEvent> {
+        let events = self.app.world().resource::<Events<LandingEvent>>();
+        self.landings.read(events).copied().collect()
+    }
+
+    /// how much the camera's shaking
+    pub fn camera_trauma(&self) -> f32 {
+        self.app.world().resource::<CameraShake>().trauma
+    }
+
+    /// the sounds requested on the last tick
+    pub fn requested_sounds(&self) -> Vec<GameSound> {
+        self.app
+            .world()
+            .resource::<RequestedSounds>()
+            .sounds
+            .clone()
+    }
+
+    /// whether the sound config has loaded
+    pub fn sound_config_loaded(&self) -> bool {
+        let world = self.app.world();
+        world
+            .get_resource::<SoundConfigHandle>()
+            .is_some_and(|handle| world.resource::<Assets<SoundConfig>>().contains(&handle.0))
+    }
+
+    /// whether a wall's collider covers this point, in world coordinates
+    pub fn wall_collider_at(&mut self, point: Vec2) -> bool {
+        let world = self.app.world_mut();
+        world
+            .query_filtered::<(&Collider, &GlobalTransform), With<WallCollider>>()
+            .iter(world)
+            .any(|(collider, transform)| {
+                collider.contains_point(transform.translation().truncate(), 0., point)
+            })
+    }
+
+    pub fn level_selection(&self) -> LevelSelection {
+        self.app.world().resource::<LevelSelection>().clone()
+    }
+
+    /// the identifier of the selected level
+    pub fn level_identifier(&self) -> String {
+        let world = self.app.world();
+        let projects = world.resource::<Assets<LdtkProject>>();
+        let (_, project) = projects
+            .iter()
+            .next()
+            .expect("the project should be loaded");
+        project
+            .find_raw_level_by_level_selection(&self.level_selection())
+            .expect("the selected level should exist")
+            .identifier
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_rapier2d::prelude::{Collider, RigidBody};
+
+    use super::*;
+    use crate::{
+        breakable::BrokenBlocks, enemy::Enemy, game_flow::RespawnLevelEvent, surface::Surface,
+    };
+
+    const FIXTURE: &str = "tests/fixture.ldtk";
+
+    #[test]
+    fn player_lands_on_the_ground() {
+        let mut game = TestGame::new(FIXTURE);
+        game.tick(30);
+        assert!(game.ground_detection().on_ground());
+        assert!(game.player_velocity().y.abs() < 1.);
+    }
+
+    #[test]
+    fn holding_right_moves_the_player_right() {
+        let mut game = TestGame::new(FIXTURE);
+        game.tick(30);
+        let start = game.player_position();
+        game.hold(PlatformerAction::Right);
+        game.tick(30);
+        assert!(game.player_position().x > start.x + 16.);
+        game.release(PlatformerAction::Right);
+        game.tick(30);
+        assert_eq!(game.player_velocity().x, 0.);
+    }
+
+    #[test]
+    fn jumping_leaves_the_ground_then_lands() {
+        let mut game = TestGame::new(FIXTURE);
+        game.tick(30);
+        let start = game.player_position();
+        game.tap(PlatformerAction::Jump);
+        game.tick(5);
+        assert!(game.jumper().is_jumping());
+        assert!(!game.ground_detection().on_ground());
+        assert!(game.player_position().y > start.y + 16.);
+        game.tick(60);
+        assert!(game.ground_detection().on_ground());
+        assert!((game.player_position().y - start.y).abs() < 1.);
+    }
+
+    #[test]
+    fn walking_off_the_edge_enters_the_next_level() {
+        let mut game = TestGame::new(FIXTURE);
+        assert_eq!(game.level_identifier(), "Level_0");
+        game.hold(PlatformerAction::Right);
+        game.tick(180);
+        assert_eq!(game.level_identifier(), "Level_4");
+    }
+
+    #[test]
+    fn falling_below_the_level_respawns_the_player() {
+        let mut game = TestGame::new(FIXTURE);
+        game.tick(30);
+        let start = game.player_position();
+        // under Level_0, past its kill plane, & not in Level_4
+        game.move_player(Vec2::new(start.x, -300.), Vec2::new(0., -500.));
+        let mut respawned = false;
+        for _ in 0..10 {
+            game.tick(1);
+            // physics hasn't moved the player since, so check the transform
+            let translation = game.player_component::<Transform>().translation;
+            if translation.y > start.y - 16. {
+                respawned = true;
+                break;
+            }
+        }
+        assert!(respawned);
+        assert_eq!(game.player_velocity(), Vec2::ZERO);
+        game.tick(30);
+        assert!(game.ground_detection().on_ground());
+        assert!((game.player_position() - start).length() < 1.);
+    }
+
+    #[test]
+    fn jumping_requests_the_jump_sound() {
+        let mut game = TestGame::new(FIXTURE);
+        game.tick(30);
+        assert!(game.sound_config_loaded());
+        assert!(!game.requested_sounds().contains(&GameSound::Jump));
+        game.tap(PlatformerAction::Jump);
+        assert!(game.requested_sounds().contains(&GameSound::Jump));
+    }
+
+    #[test]
+    fn walking_off_a_step_requests_the_land_sound_once() {
+        let mut game = TestGame::new(FIXTURE);
+        game.tick(30);
+        let start = game.player_position();
+        // the ledge the player spawns on has a one tile wide step down to its
+        // left, so let go once the player's walked off
+        game.hold(PlatformerAction::Left);
+        let mut lands = 0;
+        for _ in 0..60 {
+            game.tick(1);
+            if !game.ground_detection().on_ground() {
+                game.release(PlatformerAction::Left);
+            }
+            lands += game
+                .requested_sounds()
+                .iter()
+                .filter(|sound| **sound == GameSound::Land)
+                .count();
+        }
+        assert!(game.ground_detection().on_ground());
+        assert!((game.player_position().y - (start.y - 16.)).abs() < 1.);
+        assert_eq!(lands, 1);
+    }
+
+    /// the fixture, with every wall made of this surface
+    fn fixture_made_of(surface: Surface) -> TestGame {
+        let mut registry = Registry::default();
+        for mapping in registry.int_cells.values_mut() {
+            mapping.surface = surface;
+        }
+        TestGame::with_registry(FIXTURE, registry)
+    }
+
+    #[test]
+    fn conveyors_carry_the_player_standing_still() {
+        let mut game = fixture_made_of(Surface::Conveyor { speed: 60. });
+        game.tick(5);
+        assert!(game.ground_detection().on_ground());
+        let start = game.player_position();
+        // the player spawns on a short ledge, so stop before it's carried off
+        game.tick(15);
+        assert!(game.ground_detection().on_ground());
+        assert!(game.player_position().x > start.x + 12.);
+    }
+
+    #[test]
+    fn ice_keeps_the_player_moving_after_releasing() {
+        let mut game = fixture_made_of(Surface::Ice);
+        game.tick(30);
+        game.hold(PlatformerAction::Right);
+        game.tick(60);
+        game.release(PlatformerAction::Right);
+        game.tick(5);
+        assert!(game.player_velocity().x > 0.);
+        let released = game.player_position();
+        game.tick(10);
+        assert!(game.player_position().x > released.x);
+    }
+
+    /// jump, then start a ground pound once the player's well off the ground.
+    /// Returns where the player was standing
+    fn jump_then_pound(game: &mut TestGame) -> Vec2 {
+        game.tick(30);
+        let start = game.player_position();
+        game.tap(PlatformerAction::Jump);
+        game.tick(10);
+        assert!(!game.ground_detection().on_ground());
+        game.tap(PlatformerAction::Down);
+        game.landings();
+        start
+    }
+
+    /// tick until the player lands, for up to half a second
+    fn tick_until_landing(game: &mut TestGame) -> Option<LandingEvent> {
+        for _ in 0..30 {
+            game.tick(1);
+            if let Some(&landing) = game.landings().first() {
+                return Some(landing);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn pressing_down_in_the_air_starts_a_ground_pound() {
+        let mut game = TestGame::new(FIXTURE);
+        jump_then_pound(&mut game);
+        assert!(game.ground_pounder().is_pounding());
+        assert!(game.player_velocity().y < 0.);
+        assert_eq!(game.player_velocity().x, 0.);
+    }
+
+    #[test]
+    fn landing_a_ground_pound_shakes_the_camera() {
+        let mut game = TestGame::new(FIXTURE);
+        jump_then_pound(&mut game);
+        assert_eq!(game.camera_trauma(), 0.);
+        assert!(tick_until_landing(&mut game).is_some());
+        game.tick(1);
+        assert!(game.ground_detection().on_ground());
+        assert!(!game.ground_pounder().is_pounding());
+        assert!(game.camera_trauma() > 0.);
+    }
+
+    #[test]
+    fn ground_pounding_breaks_breakable_blocks() {
+        let mut registry = Registry::default();
+        for mapping in registry.int_cells.values_mut() {
+            mapping.kind = IntCellKind::Breakable;
+        }
+        let mut game = TestGame::with_registry(FIXTURE, registry);
+        let start = jump_then_pound(&mut game);
+        game.tick(30);
+        assert!(!game.app.world().resource::<BrokenBlocks>().0.is_empty());
+        // with the blocks under them gone, they fall through
+        assert!(game.player_position().y < start.y - 8.);
+    }
+
+    #[test]
+    fn respawning_mid_pound_stops_the_pound() {
+        let mut registry = Registry::default();
+        for mapping in registry.int_cells.values_mut() {
+            mapping.kind = IntCellKind::Breakable;
+        }
+        let mut game = TestGame::with_registry(FIXTURE, registry);
+        let start = jump_then_pound(&mut game);
+        game.app
+            .world_mut()
+            .send_event(RespawnLevelEvent::RespawnLevelEvent);
+        game.tick(1);
+        assert!(!game.ground_pounder().is_pounding());
+        game.tick(30);
+        // the player lands at the respawn point without breaking anything
+        assert!(game.app.world().resource::<BrokenBlocks>().0.is_empty());
+        assert!(game.ground_detection().on_ground());
+        assert!((game.player_position().y - start.y).abs() < 1.);
+    }
+
+    #[test]
+    fn ground_pounding_an_enemy_bounces_off_it() {
+        let mut game = TestGame::new(FIXTURE);
+        let start = jump_then_pound(&mut game);
+        // an enemy standing where the player lands
+        game.app.world_mut().spawn((
+            Enemy,
+            RigidBody::Fixed,
+            Collider::cuboid(8., 4.),
+            Transform::from_translation((start + Vec2::new(0., 8.)).extend(0.)),
+        ));
+        let landing = tick_until_landing(&mut game).expect("the player should land");
+        assert!(game.app.world().get::<Enemy>(landing.ground).is_some());
+        game.tick(1);
+        assert!(game.player_velocity().y > 0.);
+        assert!(!game.ground_pounder().is_pounding());
+        assert!(game.camera_trauma() > 0.);
+    }
+}
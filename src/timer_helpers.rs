@@ -10,6 +10,6 @@ impl TimerHelper for Timer {
         self.unpause();
     }
     fn is_stopped(&mut self) -> bool {
-        return self.paused() || self.finished();
+        self.paused() || self.finished()
     }
 }
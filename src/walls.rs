@@ -1,14 +1,251 @@
 use bevy::{prelude::*, utils::HashMap};
-use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_ldtk::{ldtk::Level, prelude::*};
 use bevy_rapier2d::prelude::*;
 use std::collections::HashSet;
 
+use crate::{
+    level_data::{self, LevelDataError, LevelDataErrors},
+    registry::{IntCellKind, Registry, RegistryAppExt},
+};
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Wall;
 
 #[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 pub struct WallBundle {
     wall: Wall,
+    // walls are merged per value
+    #[from_int_grid_cell]
+    int_grid_cell: IntGridCell,
+}
+
+/// tag for walls that can be jumped through from below & stood on. Also put on
+/// their colliders, for [`crate::colliders::ContactHooks`]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct OneWay;
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct OneWayBundle {
+    wall: Wall,
+    one_way: OneWay,
+    #[from_int_grid_cell]
+    int_grid_cell: IntGridCell,
+}
+
+/// tag for the merged colliders spawned for a level's walls
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct WallCollider;
+
+/// the walls that are merged together: the ones in the same level, IntGrid
+/// layer & with the same value
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+struct WallGroup {
+    level: Entity,
+    layer: Entity,
+    value: i32,
+}
+
+/// the group every wall tile is in, so we know which colliders to rebuild once
+/// a tile's gone
+#[derive(Resource, Default, Debug)]
+pub struct WallGroups(HashMap<Entity, WallGroup>);
+
+/// the group & rectangle a [`WallCollider`] was merged from
+#[derive(Clone, Debug, Component)]
+pub struct MergedWalls {
+    group: WallGroup,
+    rect: Rect,
+}
+
+/// the level field that picks a level's [`WallDecomposition`], e.g. `rectangles`
+pub const WALL_COLLIDERS_FIELD: &str = "wall_colliders";
+
+/// How a level's wall tiles are merged into colliders. Picked per level with
+/// the `wall_colliders` string field, falling back to
+/// [`DefaultWallDecomposition`]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
+pub enum WallDecomposition {
+    /// stacks plates with the same extents in consecutive rows. Irregular
+    /// terrain ends up with lots of colliders
+    #[default]
+    Plates,
+    /// grows the widest, then tallest rectangle it can from each tile that
+    /// isn't covered yet. Fewer colliders, & walls with a flat face are mostly
+    /// a single collider, so there's fewer seams for things to catch on
+    Rectangles,
+}
+
+impl WallDecomposition {
+    pub fn from_field(value: &str) -> Option<Self> {
+        match value {
+            "plates" => Some(Self::Plates),
+            "rectangles" => Some(Self::Rectangles),
+            _ => None,
+        }
+    }
+}
+
+/// the decomposition for levels without a `wall_colliders` field
+#[derive(Resource, Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct DefaultWallDecomposition(pub WallDecomposition);
+
+/// Represents a wide wall that is 1 tile tall
+/// Used to spawn wall collisions
+#[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
+struct Plate {
+    left: i32,
+    right: i32,
+}
+
+/// A simple rectangle type representing a wall of any size
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+struct Rect {
+    left: i32,
+    right: i32,
+    top: i32,
+    bottom: i32,
+}
+
+impl Rect {
+    /// the rectangle's collider & its translation relative to the level
+    fn collider(&self, grid_size: i32) -> (Collider, Vec2) {
+        let grid_size = grid_size as f32;
+        let collider = Collider::cuboid(
+            (self.right as f32 - self.left as f32 + 1.) * grid_size / 2.,
+            (self.top as f32 - self.bottom as f32 + 1.) * grid_size / 2.,
+        );
+        let translation = Vec2::new(
+            (self.left + self.right + 1) as f32 * grid_size / 2.,
+            (self.bottom + self.top + 1) as f32 * grid_size / 2.,
+        );
+        (collider, translation)
+    }
+}
+
+/// [`WallDecomposition::Plates`], in basic terms:
+/// 1. combine wall tiles into flat "plates" in each individual row
+/// 2. combine the plates into rectangles across multiple rows wherever possible
+///
+/// One-way walls are only merged within rows, so every one of them keeps its
+/// top surface
+fn plates(walls: &HashSet<GridCoords>, width: i32, height: i32, one_way: bool) -> Vec<Rect> {
+    // combine wall tiles into flat "plates" in each individual row
+    let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
+
+    for y in 0..height {
+        let mut row_plates: Vec<Plate> = Vec::new();
+        let mut plate_start = None;
+
+        // + 1 to the width so the algorithm "terminates" plates that touch the right edge
+        for x in 0..width + 1 {
+            match (plate_start, walls.contains(&GridCoords { x, y })) {
+                (Some(s), false) => {
+                    row_plates.push(Plate {
+                        left: s,
+                        right: x - 1,
+                    });
+                    plate_start = None;
+                }
+                (None, true) => plate_start = Some(x),
+                _ => (),
+            }
+        }
+
+        plate_stack.push(row_plates);
+    }
+
+    // combine "plates" into rectangles across multiple rows. One-way
+    // plates are left as they are
+    let mut rect_builder: HashMap<Plate, Rect> = HashMap::new();
+    let mut prev_row: Vec<Plate> = Vec::new();
+    let mut wall_rects: Vec<Rect> = Vec::new();
+
+    // an extra empty row so the algorithm "finishes" the rects that touch the top edge
+    plate_stack.push(Vec::new());
+
+    for (y, current_row) in plate_stack.into_iter().enumerate() {
+        for prev_plate in &prev_row {
+            if one_way || !current_row.contains(prev_plate) {
+                // remove the finished rect so that the same plate in the future starts a new rect
+                if let Some(rect) = rect_builder.remove(prev_plate) {
+                    wall_rects.push(rect);
+                }
+            }
+        }
+        for plate in &current_row {
+            rect_builder
+                .entry(plate.clone())
+                .and_modify(|e| e.top += 1)
+                .or_insert(Rect {
+                    bottom: y as i32,
+                    top: y as i32,
+                    left: plate.left,
+                    right: plate.right,
+                });
+        }
+        prev_row = current_row;
+    }
+
+    wall_rects
+}
+
+/// [`WallDecomposition::Rectangles`]. Going through the tiles from the bottom
+/// left, every tile that isn't covered yet starts a rectangle, which is
+/// stretched right as far as the walls go, then up as far as every tile in
+/// the next row is an uncovered wall.
+///
+/// Unlike plates, this can cover part of a wider row, so overhangs & slopes
+/// don't split everything below them
+fn greedy_rectangles(walls: &HashSet<GridCoords>, one_way: bool) -> Vec<Rect> {
+    let mut tiles: Vec<GridCoords> = walls.iter().copied().collect();
+    tiles.sort_by_key(|tile| (tile.y, tile.x));
+
+    let mut covered: HashSet<GridCoords> = HashSet::new();
+    let free = |covered: &HashSet<GridCoords>, x, y| {
+        let tile = GridCoords { x, y };
+        walls.contains(&tile) && !covered.contains(&tile)
+    };
+
+    let mut wall_rects = Vec::new();
+    for tile in tiles {
+        if covered.contains(&tile) {
+            continue;
+        }
+        let mut rect = Rect {
+            left: tile.x,
+            right: tile.x,
+            bottom: tile.y,
+            top: tile.y,
+        };
+        while free(&covered, rect.right + 1, rect.bottom) {
+            rect.right += 1;
+        }
+        // one-way walls stay a single row, so every one of them keeps its top
+        // surface
+        while !one_way && (rect.left..=rect.right).all(|x| free(&covered, x, rect.top + 1)) {
+            rect.top += 1;
+        }
+        for y in rect.bottom..=rect.top {
+            for x in rect.left..=rect.right {
+                covered.insert(GridCoords { x, y });
+            }
+        }
+        wall_rects.push(rect);
+    }
+    wall_rects
+}
+
+fn decompose(
+    decomposition: WallDecomposition,
+    walls: &HashSet<GridCoords>,
+    width: i32,
+    height: i32,
+    one_way: bool,
+) -> Vec<Rect> {
+    match decomposition {
+        WallDecomposition::Plates => plates(walls, width, height, one_way),
+        WallDecomposition::Rectangles => greedy_rectangles(walls, one_way),
+    }
 }
 
 /// Spawns heron collisions for the walls of a level
@@ -20,177 +257,506 @@ pub struct WallBundle {
 /// Instead, by flagging the wall tiles and spawning the collisions later,
 /// we can minimize the amount of colliding entities.
 ///
-/// The algorithm used here is a nice compromise between simplicity, speed,
-/// and a small number of rectangle colliders.
-/// In basic terms, it will:
-/// 1. consider where the walls are
-/// 2. combine wall tiles into flat "plates" in each individual row
-/// 3. combine the plates into rectangles across multiple rows wherever possible
-/// 4. spawn colliders for each rectangle
-pub fn spawn_wall_collision(
+/// Whenever wall tiles are added or removed, e.g. when a level's hot
+/// reloaded or a tile's destroyed, the walls they're merged with are merged
+/// again, with the level's [`WallDecomposition`]. Each IntGrid layer & value is
+/// merged separately, so every collider gets the [`crate::surface::Surface`]
+/// its value has in the registry. Only the layers & values whose tiles changed
+/// are merged again, & colliders whose rectangle is the same are kept, so
+/// whatever's standing on them stays on the ground
+#[allow(clippy::too_many_arguments)]
+pub fn rebuild_wall_collision(
     mut commands: Commands,
-    wall_query: Query<(&GridCoords, &Parent), Added<Wall>>,
-    parent_query: Query<&Parent, Without<Wall>>,
-    level_query: Query<(Entity, &LevelIid)>,
+    registry: Res<Registry>,
+    default_decomposition: Res<DefaultWallDecomposition>,
+    mut wall_groups: ResMut<WallGroups>,
+    mut removed_walls: RemovedComponents<Wall>,
+    added_walls: Query<(Entity, &Parent, &IntGridCell), Added<Wall>>,
+    wall_query: Query<(Entity, &GridCoords), With<Wall>>,
+    layer_query: Query<(&Parent, &LayerMetadata), Without<Wall>>,
+    level_query: Query<&LevelIid>,
+    old_colliders: Query<(Entity, &MergedWalls)>,
     ldtk_projects: Query<&LdtkProjectHandle>,
     ldtk_project_assets: Res<Assets<LdtkProject>>,
+    mut level_data_errors: ResMut<LevelDataErrors>,
 ) {
-    /// Represents a wide wall that is 1 tile tall
-    /// Used to spawn wall collisions
-    #[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
-    struct Plate {
-        left: i32,
-        right: i32,
+    // find the groups whose walls changed
+    let mut changed_groups: HashSet<WallGroup> = HashSet::new();
+    for (wall, parent, int_grid_cell) in &added_walls {
+        // An intgrid tile's direct parent will be a layer entity, not the level entity
+        // To get the level entity, you need the tile's grandparent.
+        // This is where layer_query comes in.
+        if let Ok((grandparent, _)) = layer_query.get(parent.get()) {
+            let group = WallGroup {
+                level: grandparent.get(),
+                layer: parent.get(),
+                value: int_grid_cell.value,
+            };
+            wall_groups.0.insert(wall, group);
+            changed_groups.insert(group);
+        }
     }
-
-    /// A simple rectangle type representing a wall of any size
-    struct Rect {
-        left: i32,
-        right: i32,
-        top: i32,
-        bottom: i32,
+    for wall in removed_walls.read() {
+        if let Some(group) = wall_groups.0.remove(&wall) {
+            changed_groups.insert(group);
+        }
+    }
+    if changed_groups.is_empty() {
+        return;
     }
 
     // Consider where the walls are
     // storing them as GridCoords in a HashSet for quick, easy lookup
     //
-    // The key of this map will be the entity of the level the wall belongs to.
-    // This has two consequences in the resulting collision entities:
+    // Grouping them by level, layer & IntGrid value has three consequences in
+    // the resulting collision entities:
     // 1. it forces the walls to be split along level boundaries
     // 2. it lets us easily add the collision entities as children of the appropriate level entity
-    let mut level_to_wall_locations: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
+    // 3. every collider is made of a single surface
+    let mut group_walls: HashMap<WallGroup, HashSet<GridCoords>> = changed_groups
+        .iter()
+        .map(|&group| (group, HashSet::new()))
+        .collect();
+    for (wall, &grid_coords) in &wall_query {
+        if let Some(walls) = wall_groups
+            .0
+            .get(&wall)
+            .and_then(|group| group_walls.get_mut(group))
+        {
+            walls.insert(grid_coords);
+        }
+    }
 
-    wall_query.iter().for_each(|(&grid_coords, parent)| {
-        // An intgrid tile's direct parent will be a layer entity, not the level entity
-        // To get the level entity, you need the tile's grandparent.
-        // This is where parent_query comes in.
-        if let Ok(grandparent) = parent_query.get(parent.get()) {
-            level_to_wall_locations
-                .entry(grandparent.get())
+    // the changed groups' current colliders, by their rectangle
+    let mut group_colliders: HashMap<WallGroup, HashMap<Rect, Entity>> = HashMap::new();
+    for (collider, merged_walls) in &old_colliders {
+        if changed_groups.contains(&merged_walls.group) {
+            group_colliders
+                .entry(merged_walls.group)
                 .or_default()
-                .insert(grid_coords);
-        }
-    });
-
-    if !wall_query.is_empty() {
-        level_query.iter().for_each(|(level_entity, level_iid)| {
-            if let Some(level_walls) = level_to_wall_locations.get(&level_entity) {
-                let ldtk_project = ldtk_project_assets
-                    .get(ldtk_projects.single())
-                    .expect("Project should be loaded if level has spawned");
-
-                let level = ldtk_project
-                    .as_standalone()
-                    .get_loaded_level_by_iid(&level_iid.to_string())
-                    .expect("Spawned level should exist in LDtk project");
-
-                let LayerInstance {
-                    c_wid: width,
-                    c_hei: height,
-                    grid_size,
-                    ..
-                } = level.layer_instances()[0];
-
-                // combine wall tiles into flat "plates" in each individual row
-                let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
-
-                for y in 0..height {
-                    let mut row_plates: Vec<Plate> = Vec::new();
-                    let mut plate_start = None;
-
-                    // + 1 to the width so the algorithm "terminates" plates that touch the right edge
-                    for x in 0..width + 1 {
-                        match (plate_start, level_walls.contains(&GridCoords { x, y })) {
-                            (Some(s), false) => {
-                                row_plates.push(Plate {
-                                    left: s,
-                                    right: x - 1,
-                                });
-                                plate_start = None;
-                            }
-                            (None, true) => plate_start = Some(x),
-                            _ => (),
-                        }
-                    }
-
-                    plate_stack.push(row_plates);
-                }
+                .insert(merged_walls.rect.clone(), collider);
+        }
+    }
 
-                // combine "plates" into rectangles across multiple rows
-                let mut rect_builder: HashMap<Plate, Rect> = HashMap::new();
-                let mut prev_row: Vec<Plate> = Vec::new();
-                let mut wall_rects: Vec<Rect> = Vec::new();
-
-                // an extra empty row so the algorithm "finishes" the rects that touch the top edge
-                plate_stack.push(Vec::new());
-
-                for (y, current_row) in plate_stack.into_iter().enumerate() {
-                    for prev_plate in &prev_row {
-                        if !current_row.contains(prev_plate) {
-                            // remove the finished rect so that the same plate in the future starts a new rect
-                            if let Some(rect) = rect_builder.remove(prev_plate) {
-                                wall_rects.push(rect);
-                            }
-                        }
-                    }
-                    for plate in &current_row {
-                        rect_builder
-                            .entry(plate.clone())
-                            .and_modify(|e| e.top += 1)
-                            .or_insert(Rect {
-                                bottom: y as i32,
-                                top: y as i32,
-                                left: plate.left,
-                                right: plate.right,
-                            });
-                    }
-                    prev_row = current_row;
-                }
+    let ldtk_project = level_data::loaded_project(&ldtk_projects, &ldtk_project_assets);
+    let mut decompositions: HashMap<Entity, WallDecomposition> = HashMap::new();
+    for (group, level_walls) in group_walls {
+        // If the level itself is gone, so are its colliders
+        let Ok(level_iid) = level_query.get(group.level) else {
+            continue;
+        };
+        let mut old_rects = group_colliders.remove(&group).unwrap_or_default();
+        let one_way = registry.int_cell_kind(group.value) == Some(IntCellKind::OneWay);
+        let surface = registry.surface(group.value);
 
-                commands.entity(level_entity).with_children(|level| {
-                    // Spawn colliders for every rectangle..
-                    // Making the collider a child of the level serves two purposes:
-                    // 1. Adjusts the transforms to be relative to the level for free
-                    // 2. the colliders will be despawned automatically when levels unload
-                    for wall_rect in wall_rects {
-                        level
-                            .spawn_empty()
-                            .insert(Collider::cuboid(
-                                (wall_rect.right as f32 - wall_rect.left as f32 + 1.)
-                                    * grid_size as f32
-                                    / 2.,
-                                (wall_rect.top as f32 - wall_rect.bottom as f32 + 1.)
-                                    * grid_size as f32
-                                    / 2.,
-                            ))
-                            .insert(RigidBody::Fixed)
-                            .insert(Friction::new(1.0))
-                            .insert(Transform::from_xyz(
-                                (wall_rect.left + wall_rect.right + 1) as f32 * grid_size as f32
-                                    / 2.,
-                                (wall_rect.bottom + wall_rect.top + 1) as f32 * grid_size as f32
-                                    / 2.,
-                                0.,
-                            ))
-                            .insert(GlobalTransform::default());
-                    }
+        // the size of the IntGrid layer the walls are in, rather than whichever
+        // layer happens to come first in the level. The layer's gone if its
+        // level's respawning, so are its walls
+        let layer_metadata = layer_query.get(group.layer).ok();
+        let wall_rects = match layer_metadata {
+            Some((_, layer_metadata)) if !level_walls.is_empty() => {
+                let decomposition = *decompositions.entry(group.level).or_insert_with(|| {
+                    let raw_level = ldtk_project
+                        .and_then(|project| project.get_raw_level_by_iid(level_iid.get()));
+                    level_decomposition(raw_level, default_decomposition.0, &mut level_data_errors)
                 });
+                decompose(
+                    decomposition,
+                    &level_walls,
+                    layer_metadata.c_wid,
+                    layer_metadata.c_hei,
+                    one_way,
+                )
+            }
+            _ => Vec::new(),
+        };
+        // keep the colliders that haven't changed
+        let new_rects: Vec<Rect> = wall_rects
+            .into_iter()
+            .filter(|wall_rect| old_rects.remove(wall_rect).is_none())
+            .collect();
+        for (_, collider) in old_rects {
+            commands.entity(collider).despawn_recursive();
+        }
+        let Some((_, layer_metadata)) = layer_metadata else {
+            continue;
+        };
+        let grid_size = layer_metadata.grid_size;
+
+        commands.entity(group.level).with_children(|level| {
+            // Spawn colliders for every rectangle..
+            // Making the collider a child of the level serves two purposes:
+            // 1. Adjusts the transforms to be relative to the level for free
+            // 2. the colliders will be despawned automatically when levels unload
+            for wall_rect in new_rects {
+                let (collider, translation) = wall_rect.collider(grid_size);
+                let mut wall = level.spawn_empty();
+                wall.insert(WallCollider)
+                    .insert(MergedWalls {
+                        group,
+                        rect: wall_rect,
+                    })
+                    .insert(collider)
+                    .insert(RigidBody::Fixed)
+                    .insert(surface)
+                    .insert(surface.friction())
+                    .insert(surface.restitution())
+                    .insert(Transform::from_translation(translation.extend(0.)))
+                    .insert(GlobalTransform::default());
+                let mut active_hooks = surface.active_hooks();
+                if one_way {
+                    wall.insert(OneWay);
+                    active_hooks |= ActiveHooks::MODIFY_SOLVER_CONTACTS;
+                }
+                wall.insert(active_hooks);
             }
         });
     }
 }
 
+/// the decomposition a level asks for with its `wall_colliders` field
+fn level_decomposition(
+    level: Option<&Level>,
+    default: WallDecomposition,
+    errors: &mut ResMut<LevelDataErrors>,
+) -> WallDecomposition {
+    let Some(level) = level else {
+        return default;
+    };
+    let Ok(Some(value)) = level.get_maybe_string_field(WALL_COLLIDERS_FIELD) else {
+        return default;
+    };
+    WallDecomposition::from_field(value).unwrap_or_else(|| {
+        level_data::report(
+            errors,
+            LevelDataError::LevelField {
+                level: level.identifier.clone(),
+                field: WALL_COLLIDERS_FIELD,
+                message: format!("{value:?} isn't plates or rectangles"),
+            },
+        );
+        default
+    })
+}
+
 /// Plugin which spawns walls on appropriate LDtk int cells,
 /// then merges them together to reduce physics load.
 ///
-/// Walls are hardcoded as int cell values 1 (dirt) and 3 (stone).
+/// Which int cells are walls comes from the [`crate::registry::Registry`].
 pub struct WallsPlugin;
 
 impl Plugin for WallsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, spawn_wall_collision)
-            .register_ldtk_int_cell::<WallBundle>(1) // Ground
-            .register_ldtk_int_cell::<WallBundle>(2) // White Box
-            .register_ldtk_int_cell::<WallBundle>(3); // Black Box
+        app.init_resource::<WallGroups>()
+            .init_resource::<DefaultWallDecomposition>()
+            .add_systems(Update, rebuild_wall_collision)
+            .register_int_cell_kind::<WallBundle>(IntCellKind::Wall)
+            .register_int_cell_kind::<OneWayBundle>(IntCellKind::OneWay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bevy_ecs_ldtk::utils::int_grid_index_to_grid_coords;
+    use bevy_rapier2d::rapier::prelude::{
+        CCDSolver, ColliderBuilder, ColliderSet, DefaultBroadPhase, ImpulseJointSet,
+        IntegrationParameters, IslandManager, MultibodyJointSet, NarrowPhase, PhysicsPipeline,
+        RigidBodyBuilder, RigidBodySet,
+    };
+
+    use super::*;
+    use crate::{ground_detection::GroundDetection, lint::load_project, test_harness::TestGame};
+
+    fn tiles(rows: &[&str]) -> HashSet<GridCoords> {
+        let height = rows.len() as i32;
+        rows.iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|(_, c)| *c == '#')
+                    .map(move |(x, _)| GridCoords::new(x as i32, height - 1 - row as i32))
+            })
+            .collect()
+    }
+
+    /// slide a player-sized box along the colliders, setting its x velocity
+    /// every step like `player_movement` does. Returns every velocity it had
+    /// after a step
+    fn slide(
+        colliders: Vec<(Collider, Vec2)>,
+        start: Vec2,
+        x_velocity: f32,
+        steps: usize,
+    ) -> Vec<Vec2> {
+        let mut bodies = RigidBodySet::new();
+        let mut collider_set = ColliderSet::new();
+        for (collider, translation) in colliders {
+            collider_set.insert(
+                ColliderBuilder::new(collider.raw.clone())
+                    .translation(translation.into())
+                    .friction(0.),
+            );
+        }
+        let body = bodies.insert(
+            RigidBodyBuilder::dynamic()
+                .translation(start.into())
+                .lock_rotations(),
+        );
+        collider_set.insert_with_parent(
+            ColliderBuilder::cuboid(6., 8.).friction(0.),
+            body,
+            &mut bodies,
+        );
+
+        let integration_parameters = IntegrationParameters {
+            length_unit: 100.,
+            dt: 1. / 64.,
+            ..default()
+        };
+        let mut physics_pipeline = PhysicsPipeline::new();
+        let mut islands = IslandManager::new();
+        let mut broad_phase = DefaultBroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut impulse_joints = ImpulseJointSet::new();
+        let mut multibody_joints = MultibodyJointSet::new();
+        let mut ccd_solver = CCDSolver::new();
+        let gravity = Vect::new(0., -981.).into();
+
+        let mut velocities = Vec::new();
+        for _ in 0..steps {
+            let linvel = *bodies[body].linvel();
+            bodies[body].set_linvel([x_velocity, linvel.y].into(), true);
+            physics_pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut islands,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut collider_set,
+                &mut impulse_joints,
+                &mut multibody_joints,
+                &mut ccd_solver,
+                None,
+                &(),
+                &(),
+            );
+            velocities.push(Vec2::from(*bodies[body].linvel()));
+        }
+        velocities
+    }
+
+    /// every bundled project's walls, for each IntGrid layer & value:
+    /// `(one_way, walls, size)`
+    fn bundled_walls() -> Vec<(bool, HashSet<GridCoords>, (i32, i32))> {
+        const PROJECTS: &[&str] = &[
+            "levels/test/level.ldtk",
+            "levels/SamTest/level.ldtk",
+            "levels/SamTest/levelEdits.ldtk",
+            "levels/SamTest/TowerOfBabel.ldtk",
+            "levels/SamTest/HallwayOfBabel.ldtk",
+            "levels/SamTest/HallwayOfBabel2.ldtk",
+            "examples/platformer/example.ldtk",
+            "tests/fixture.ldtk",
+        ];
+        let mut bundled = Vec::new();
+        for project in PROJECTS {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("assets")
+                .join(project);
+            let (_, levels, registry) = load_project(&path).unwrap();
+            for layer in levels
+                .iter()
+                .flat_map(|level| level.layer_instances.iter().flatten())
+            {
+                let (width, height) = (layer.c_wid, layer.c_hei);
+                let mut by_value: HashMap<i32, HashSet<GridCoords>> = HashMap::new();
+                for (i, &value) in layer.int_grid_csv.iter().enumerate() {
+                    if let Some(grid_coords) =
+                        int_grid_index_to_grid_coords(i, width as u32, height as u32)
+                    {
+                        by_value.entry(value).or_default().insert(grid_coords);
+                    }
+                }
+                for (value, walls) in by_value {
+                    match registry.int_cell_kind(value) {
+                        Some(IntCellKind::Wall) => bundled.push((false, walls, (width, height))),
+                        Some(IntCellKind::OneWay) => bundled.push((true, walls, (width, height))),
+                        _ => (),
+                    }
+                }
+            }
+        }
+        bundled
+    }
+
+    /// the tiles covered by rectangles, panicking if any are covered twice
+    fn covered(wall_rects: &[Rect]) -> HashSet<GridCoords> {
+        let mut covered = HashSet::new();
+        for rect in wall_rects {
+            for y in rect.bottom..=rect.top {
+                for x in rect.left..=rect.right {
+                    assert!(
+                        covered.insert(GridCoords { x, y }),
+                        "{x}, {y} covered twice"
+                    );
+                }
+            }
+        }
+        covered
+    }
+
+    fn colliders(wall_rects: Vec<Rect>) -> Vec<(Collider, Vec2)> {
+        wall_rects.iter().map(|rect| rect.collider(16)).collect()
+    }
+
+    #[test]
+    fn decompositions_cover_every_wall_once() {
+        for (one_way, walls, (width, height)) in bundled_walls() {
+            assert_eq!(covered(&plates(&walls, width, height, one_way)), walls);
+            assert_eq!(covered(&greedy_rectangles(&walls, one_way)), walls);
+        }
+    }
+
+    #[test]
+    fn one_way_walls_stay_in_rows() {
+        for (_, walls, (width, height)) in bundled_walls() {
+            for decomposition in [WallDecomposition::Plates, WallDecomposition::Rectangles] {
+                for rect in decompose(decomposition, &walls, width, height, true) {
+                    assert_eq!(rect.top, rect.bottom);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rectangles_need_fewer_colliders_on_the_bundled_levels() {
+        let (mut plate_count, mut rectangle_count) = (0, 0);
+        for (one_way, walls, (width, height)) in bundled_walls() {
+            plate_count += plates(&walls, width, height, one_way).len();
+            rectangle_count += greedy_rectangles(&walls, one_way).len();
+        }
+        assert!(
+            rectangle_count < plate_count,
+            "{rectangle_count} rectangles, {plate_count} plates"
+        );
+    }
+
+    #[test]
+    fn rectangles_merge_rows_of_different_widths() {
+        // plates can only stack the rows with identical extents, so this is
+        // five colliders
+        let walls = tiles(&["  ###", " ####", "#####", " ####", "  ###"]);
+        assert_eq!(plates(&walls, 5, 5, false).len(), 5);
+        assert_eq!(greedy_rectangles(&walls, false).len(), 3);
+    }
+
+    #[test]
+    fn sliding_down_a_flush_wall_doesnt_catch_on_seams() {
+        // a wall with a flush right face, which plates turn into a stack of
+        // rectangles with a seam at every row
+        let mut rows = [" ###", "  ##"].repeat(12);
+        rows.push("####");
+        let wall = tiles(&rows);
+        let wall_rects = greedy_rectangles(&wall, false);
+        // pressed up against the face & falling
+        let velocities = slide(colliders(wall_rects), Vec2::new(64. + 6., 380.), -150., 60);
+        for (step, velocity) in velocities.iter().enumerate() {
+            assert!(velocity.y < 0., "stopped falling at step {step}");
+        }
+    }
+
+    #[test]
+    fn running_across_seams_doesnt_catch() {
+        // the top row is split where the columns below it start
+        let floor = tiles(&[
+            "################################",
+            "####    ####    ####    ####    ",
+            "####    ####    ####    ####    ",
+        ]);
+        for decomposition in [WallDecomposition::Plates, WallDecomposition::Rectangles] {
+            let wall_rects = decompose(decomposition, &floor, 32, 3, false);
+            let velocities = slide(colliders(wall_rects), Vec2::new(20., 48. + 8.), 150., 150);
+            for (step, velocity) in velocities.iter().enumerate() {
+                assert_eq!(
+                    velocity.x, 150.,
+                    "{decomposition:?} slowed down at step {step}"
+                );
+                assert!(
+                    velocity.y.abs() < 1.,
+                    "{decomposition:?} bounced at step {step}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn removing_a_wall_keeps_the_colliders_it_wasnt_in() {
+        let mut game = TestGame::new("tests/fixture.ldtk");
+        game.tick(30);
+        let GroundDetection::OnGround(ground) = game.ground_detection() else {
+            panic!("the player should be on the ground");
+        };
+        let world = game.app.world_mut();
+        let merged_walls = world
+            .get::<MergedWalls>(ground)
+            .expect("the player should stand on a wall collider")
+            .clone();
+
+        // the wall furthest from the player that's merged with the ground
+        let wall_groups = world.resource::<WallGroups>().0.clone();
+        let rect = &merged_walls.rect;
+        let wall = world
+            .query_filtered::<(Entity, &GridCoords), With<Wall>>()
+            .iter(world)
+            .filter(|(wall, _)| wall_groups.get(wall) == Some(&merged_walls.group))
+            .max_by_key(|(_, grid_coords)| {
+                (grid_coords.x - rect.left).abs() + (grid_coords.y - rect.bottom).abs()
+            })
+            .map(|(wall, _)| wall)
+            .expect("the ground should be merged with other walls");
+        world.entity_mut(wall).despawn_recursive();
+
+        game.tick(2);
+        assert!(game.app.world().get_entity(ground).is_ok());
+        assert!(matches!(
+            game.ground_detection(),
+            GroundDetection::OnGround(entity) if entity == ground
+        ));
+    }
+
+    #[test]
+    fn merging_the_ground_again_doesnt_land_the_player() {
+        let mut game = TestGame::new("tests/fixture.ldtk");
+        game.tick(30);
+        let GroundDetection::OnGround(ground) = game.ground_detection() else {
+            panic!("the player should be on the ground");
+        };
+        game.landings();
+        let world = game.app.world_mut();
+        let merged_walls = world
+            .get::<MergedWalls>(ground)
+            .expect("the player should stand on a wall collider")
+            .clone();
+
+        // a tile at the end of the ground, away from the player
+        let rect = &merged_walls.rect;
+        let wall = world
+            .query_filtered::<(Entity, &GridCoords), With<Wall>>()
+            .iter(world)
+            .find(|(_, grid_coords)| grid_coords.x == rect.left && grid_coords.y == rect.bottom)
+            .map(|(wall, _)| wall)
+            .expect("the ground's corner should be a wall");
+        world.entity_mut(wall).despawn_recursive();
+
+        for _ in 0..10 {
+            game.tick(1);
+            assert!(game.ground_detection().on_ground());
+        }
+        assert!(game.app.world().get_entity(ground).is_err());
+        assert_eq!(game.landings(), []);
     }
 }